@@ -0,0 +1,8 @@
+#version 140
+in vec4 vColor;
+
+out vec4 color;
+
+void main() {
+    color = vColor;
+}
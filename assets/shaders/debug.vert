@@ -0,0 +1,13 @@
+#version 140
+in vec3 position;
+in vec4 color;
+
+out vec4 vColor;
+
+uniform mat4 view;
+uniform mat4 proj;
+
+void main() {
+    vColor = color;
+    gl_Position = proj * view * vec4(position, 1.0);
+}
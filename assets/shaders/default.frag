@@ -0,0 +1,235 @@
+#version 140
+
+// metallic-roughness shading following the glTF material model, all lighting is done in linear
+// space and the sRGB framebuffer encodes the result
+
+const float PI = 3.14159265359;
+
+struct DirectionalLight {
+    vec3 direction;
+    vec3 color;
+    int shadow_index;
+};
+
+struct PointLight {
+    vec3 position;
+    vec3 color;
+    float range;
+};
+
+struct SpotLight {
+    vec3 position;
+    vec3 direction;
+    vec3 color;
+    float range;
+    float inner_cos;
+    float outer_cos;
+    int shadow_index;
+};
+
+struct Shadow {
+    mat4 matrix;
+    float bias;
+};
+
+uniform DirectionalLight directional_lights[MAX_DIRECTIONAL_LIGHTS];
+uniform int directional_light_count;
+uniform PointLight point_lights[MAX_POINT_LIGHTS];
+uniform int point_light_count;
+uniform SpotLight spot_lights[MAX_SPOT_LIGHTS];
+uniform int spot_light_count;
+uniform vec3 ambient_light;
+
+// image based lighting from the environment, black when there isn't one
+uniform samplerCube environment_map;
+uniform samplerCube irradiance_map;
+uniform float environment_intensity;
+uniform float environment_max_lod;
+
+uniform sampler2DArrayShadow shadow_maps;
+uniform Shadow shadows[MAX_SHADOW_MAPS];
+uniform int pcf_radius;
+uniform float shadow_texel_size;
+
+uniform vec3 camera_position;
+// set by cutout materials, 0 otherwise
+uniform float alpha_cutoff;
+
+uniform vec4 base_color_factor;
+uniform float metallic_factor;
+uniform float roughness_factor;
+uniform vec3 emissive_factor;
+uniform float occlusion_strength;
+uniform float normal_scale;
+
+uniform sampler2D base_color_map;
+// roughness in green, metallic in blue
+uniform sampler2D metallic_roughness_map;
+uniform sampler2D normal_map;
+uniform sampler2D occlusion_map;
+uniform sampler2D emissive_map;
+
+in vec3 vPosition;
+in vec3 vNormal;
+in vec2 vUv;
+in vec4 vTangent;
+
+out vec4 color;
+
+// smoothly reaches zero at `range` so lights can be culled there
+float attenuation(float distance, float range) {
+    float falloff = clamp(1.0 - pow(distance / range, 4.0), 0.0, 1.0);
+    return falloff * falloff / (distance * distance + 1.0);
+}
+
+// how much of the light reaches this fragment, averaged over the pcf kernel
+float shadow_factor(int index) {
+    if (index < 0) {
+        return 1.0;
+    }
+
+    vec4 light_space = shadows[index].matrix * vec4(vPosition, 1.0);
+    vec3 coords = light_space.xyz / light_space.w * 0.5 + 0.5;
+    if (coords.z > 1.0) {
+        return 1.0;
+    }
+
+    float depth = coords.z - shadows[index].bias;
+    float lit = 0.0;
+    for (int x = -pcf_radius; x <= pcf_radius; x++) {
+        for (int y = -pcf_radius; y <= pcf_radius; y++) {
+            vec2 offset = vec2(x, y) * shadow_texel_size;
+            lit += texture(shadow_maps, vec4(coords.xy + offset, float(index), depth));
+        }
+    }
+
+    float taps = float((2 * pcf_radius + 1) * (2 * pcf_radius + 1));
+    return lit / taps;
+}
+
+// applies the normal map in the mikktspace tangent frame, meshes without tangents are left as is
+vec3 perturb_normal(vec3 normal) {
+    if (dot(vTangent.xyz, vTangent.xyz) < 1e-8) {
+        return normal;
+    }
+
+    vec3 sampled = texture(normal_map, vUv).xyz * 2.0 - 1.0;
+    sampled.xy *= normal_scale;
+
+    // interpolation leaves the tangent slightly off perpendicular
+    vec3 tangent = normalize(vTangent.xyz - normal * dot(normal, vTangent.xyz));
+    vec3 bitangent = cross(normal, tangent) * vTangent.w;
+
+    return normalize(mat3(tangent, bitangent, normal) * sampled);
+}
+
+float distribution_ggx(float n_dot_h, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+float geometry_smith(float n_dot_v, float n_dot_l, float roughness) {
+    float k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    float gv = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    float gl = n_dot_l / (n_dot_l * (1.0 - k) + k);
+    return gv * gl;
+}
+
+vec3 fresnel_schlick(float cos_theta, vec3 f0) {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// light reflected towards the viewer from a light arriving from `to_light` with `radiance`
+vec3 brdf(vec3 normal, vec3 view, vec3 to_light, vec3 radiance, vec3 albedo, float metallic,
+        float roughness) {
+    vec3 halfway = normalize(view + to_light);
+    float n_dot_l = max(dot(normal, to_light), 0.0);
+    float n_dot_v = max(dot(normal, view), 0.0001);
+    float n_dot_h = max(dot(normal, halfway), 0.0);
+
+    vec3 f0 = mix(vec3(0.04), albedo, metallic);
+    vec3 fresnel = fresnel_schlick(max(dot(halfway, view), 0.0), f0);
+
+    vec3 specular = distribution_ggx(n_dot_h, roughness) * geometry_smith(n_dot_v, n_dot_l, roughness)
+        * fresnel / (4.0 * n_dot_v * n_dot_l + 0.0001);
+    vec3 diffuse = (1.0 - fresnel) * (1.0 - metallic) * albedo / PI;
+
+    // PI cancels the one in the diffuse term so a light's color is the brightness it gives a
+    // white surface facing it
+    return (diffuse + specular) * radiance * n_dot_l * PI;
+}
+
+// Karis' analytic fit of the split sum environment BRDF, the scale and bias applied to f0
+vec2 environment_brdf(float n_dot_v, float roughness) {
+    const vec4 c0 = vec4(-1.0, -0.0275, -0.572, 0.022);
+    const vec4 c1 = vec4(1.0, 0.0425, 1.04, -0.04);
+    vec4 r = roughness * c0 + c1;
+    float a004 = min(r.x * r.x, exp2(-9.28 * n_dot_v)) * r.x + r.y;
+    return vec2(-1.04, 1.04) * a004 + r.zw;
+}
+
+// light from the environment, rougher surfaces reflect blurrier mip levels
+vec3 environment_light(vec3 normal, vec3 view, vec3 albedo, float metallic, float roughness) {
+    float n_dot_v = max(dot(normal, view), 0.0001);
+    vec3 f0 = mix(vec3(0.04), albedo, metallic);
+    vec2 scale_bias = environment_brdf(n_dot_v, roughness);
+
+    vec3 reflected = textureLod(environment_map, reflect(-view, normal),
+        roughness * environment_max_lod).rgb;
+    vec3 specular = reflected * (f0 * scale_bias.x + scale_bias.y);
+
+    vec3 irradiance = texture(irradiance_map, normal).rgb;
+    vec3 diffuse = irradiance * albedo * (1.0 - metallic) * (1.0 - f0);
+
+    return (diffuse + specular) * environment_intensity;
+}
+
+void main() {
+    vec4 base_color = texture(base_color_map, vUv) * base_color_factor;
+    if (base_color.a < alpha_cutoff) {
+        discard;
+    }
+    vec4 metallic_roughness = texture(metallic_roughness_map, vUv);
+    float metallic = clamp(metallic_roughness.b * metallic_factor, 0.0, 1.0);
+    float roughness = clamp(metallic_roughness.g * roughness_factor, 0.04, 1.0);
+    float occlusion = mix(1.0, texture(occlusion_map, vUv).r, occlusion_strength);
+    vec3 emissive = texture(emissive_map, vUv).rgb * emissive_factor;
+
+    vec3 view = normalize(camera_position - vPosition);
+    vec3 normal = perturb_normal(normalize(vNormal));
+
+    vec3 albedo = base_color.rgb;
+    vec3 light = (ambient_light * albedo + environment_light(normal, view, albedo, metallic, roughness))
+        * occlusion;
+
+    for (int i = 0; i < directional_light_count; i++) {
+        vec3 radiance = directional_lights[i].color * shadow_factor(directional_lights[i].shadow_index);
+        light += brdf(normal, view, -directional_lights[i].direction, radiance, albedo, metallic,
+            roughness);
+    }
+
+    for (int i = 0; i < point_light_count; i++) {
+        vec3 to_light = point_lights[i].position - vPosition;
+        float distance = length(to_light);
+
+        vec3 radiance = point_lights[i].color * attenuation(distance, point_lights[i].range);
+        light += brdf(normal, view, to_light / distance, radiance, albedo, metallic, roughness);
+    }
+
+    for (int i = 0; i < spot_light_count; i++) {
+        vec3 to_light = spot_lights[i].position - vPosition;
+        float distance = length(to_light);
+        vec3 direction = to_light / distance;
+
+        float cone = smoothstep(spot_lights[i].outer_cos, spot_lights[i].inner_cos,
+            dot(-direction, spot_lights[i].direction));
+
+        vec3 radiance = spot_lights[i].color * attenuation(distance, spot_lights[i].range) * cone
+            * shadow_factor(spot_lights[i].shadow_index);
+        light += brdf(normal, view, direction, radiance, albedo, metallic, roughness);
+    }
+
+    color = vec4(light + emissive, base_color.a);
+}
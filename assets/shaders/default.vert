@@ -0,0 +1,31 @@
+#version 140
+uniform mat4 view;
+uniform mat4 proj;
+
+in vec3 position;
+in vec3 normal;
+in vec2 uv;
+in vec4 tangent;
+// per instance
+in mat4 instance_model;
+
+out vec3 vPosition;
+out vec3 vNormal;
+out vec2 vUv;
+out vec4 vTangent;
+
+void main() {
+    vec4 world_position = instance_model * vec4(position, 1.0);
+
+    // normals need the inverse transpose to stay perpendicular under non-uniform scale, tangents
+    // lie in the surface so they follow the model matrix and get re-orthogonalised per fragment
+    mat3 model = mat3(instance_model);
+    mat3 normal_matrix = transpose(inverse(model));
+
+    vPosition = world_position.xyz;
+    vNormal = normal_matrix * normal;
+    vUv = uv;
+    vTangent = vec4(model * tangent.xyz, tangent.w);
+
+    gl_Position = proj * view * world_position;
+}
@@ -0,0 +1,13 @@
+#version 140
+uniform sampler2D source;
+uniform sampler2D bloom_map;
+uniform float intensity;
+
+in vec2 vUv;
+
+out vec4 color;
+
+void main() {
+    vec4 scene = texture(source, vUv);
+    color = vec4(scene.rgb + texture(bloom_map, vUv).rgb * intensity, scene.a);
+}
@@ -0,0 +1,21 @@
+#version 140
+uniform sampler2D source;
+// one texel along the blurred axis
+uniform vec2 direction;
+
+in vec2 vUv;
+
+out vec4 color;
+
+// half of a separable 9 tap gaussian
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+    vec3 result = texture(source, vUv).rgb * weights[0];
+    for (int i = 1; i < 5; i++) {
+        result += texture(source, vUv + direction * float(i)).rgb * weights[i];
+        result += texture(source, vUv - direction * float(i)).rgb * weights[i];
+    }
+
+    color = vec4(result, 1.0);
+}
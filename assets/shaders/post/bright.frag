@@ -0,0 +1,16 @@
+#version 140
+uniform sampler2D source;
+uniform float threshold;
+
+in vec2 vUv;
+
+out vec4 color;
+
+// keeps the part of each color above the threshold
+void main() {
+    vec3 scene = texture(source, vUv).rgb;
+    float brightness = max(scene.r, max(scene.g, scene.b));
+    float contribution = max(brightness - threshold, 0.0) / max(brightness, 0.0001);
+
+    color = vec4(scene * contribution, 1.0);
+}
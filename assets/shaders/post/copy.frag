@@ -0,0 +1,10 @@
+#version 140
+uniform sampler2D source;
+
+in vec2 vUv;
+
+out vec4 color;
+
+void main() {
+    color = texture(source, vUv);
+}
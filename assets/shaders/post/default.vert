@@ -0,0 +1,10 @@
+#version 140
+out vec2 vUv;
+
+void main() {
+    // one triangle big enough to cover the screen
+    vec2 position = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+
+    vUv = position;
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
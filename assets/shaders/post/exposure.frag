@@ -0,0 +1,12 @@
+#version 140
+uniform sampler2D source;
+uniform float exposure;
+
+in vec2 vUv;
+
+out vec4 color;
+
+void main() {
+    vec4 scene = texture(source, vUv);
+    color = vec4(scene.rgb * exposure, scene.a);
+}
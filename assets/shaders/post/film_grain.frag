@@ -0,0 +1,20 @@
+#version 140
+uniform sampler2D source;
+uniform float intensity;
+// changes the noise every frame
+uniform float time;
+
+in vec2 vUv;
+
+out vec4 color;
+
+float random(vec2 seed) {
+    return fract(sin(dot(seed, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+void main() {
+    vec4 scene = texture(source, vUv);
+    float noise = random(vUv * 1000.0 + fract(time) * 100.0) - 0.5;
+
+    color = vec4(max(scene.rgb + noise * intensity, 0.0), scene.a);
+}
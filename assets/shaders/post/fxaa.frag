@@ -0,0 +1,54 @@
+#version 140
+uniform sampler2D source;
+uniform vec2 texel_size;
+
+in vec2 vUv;
+
+out vec4 color;
+
+#define FXAA_REDUCE_MIN (1.0 / 128.0)
+#define FXAA_REDUCE_MUL (1.0 / 8.0)
+#define FXAA_SPAN_MAX 8.0
+
+// edges are found by perceived brightness, the colors are linear
+float luma(vec3 rgb) {
+    return dot(sqrt(rgb), vec3(0.299, 0.587, 0.114));
+}
+
+void main() {
+    vec4 center = texture(source, vUv);
+    float luma_m = luma(center.rgb);
+    float luma_nw = luma(texture(source, vUv + vec2(-1.0, -1.0) * texel_size).rgb);
+    float luma_ne = luma(texture(source, vUv + vec2(1.0, -1.0) * texel_size).rgb);
+    float luma_sw = luma(texture(source, vUv + vec2(-1.0, 1.0) * texel_size).rgb);
+    float luma_se = luma(texture(source, vUv + vec2(1.0, 1.0) * texel_size).rgb);
+
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // blurs along the edge, across the direction the brightness changes in
+    vec2 dir = vec2(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+    float dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL, FXAA_REDUCE_MIN);
+    float inverse_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * inverse_dir_min, -FXAA_SPAN_MAX, FXAA_SPAN_MAX) * texel_size;
+
+    vec3 rgb_a = 0.5 * (
+        texture(source, vUv + dir * (1.0 / 3.0 - 0.5)).rgb +
+        texture(source, vUv + dir * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 rgb_b = rgb_a * 0.5 + 0.25 * (
+        texture(source, vUv - dir * 0.5).rgb +
+        texture(source, vUv + dir * 0.5).rgb
+    );
+
+    // the wider blur went past the edge
+    float luma_b = luma(rgb_b);
+    if (luma_b < luma_min || luma_b > luma_max) {
+        color = vec4(rgb_a, center.a);
+    } else {
+        color = vec4(rgb_b, center.a);
+    }
+}
@@ -0,0 +1,26 @@
+#version 140
+uniform sampler2D source;
+// 0 is Reinhard, 1 is ACES
+uniform int tonemapper;
+
+in vec2 vUv;
+
+out vec4 color;
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve
+vec3 aces(vec3 x) {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+void main() {
+    vec4 scene = texture(source, vUv);
+
+    vec3 mapped;
+    if (tonemapper == 1) {
+        mapped = aces(scene.rgb);
+    } else {
+        mapped = scene.rgb / (scene.rgb + 1.0);
+    }
+
+    color = vec4(mapped, scene.a);
+}
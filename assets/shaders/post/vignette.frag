@@ -0,0 +1,17 @@
+#version 140
+uniform sampler2D source;
+uniform float intensity;
+uniform float radius;
+
+in vec2 vUv;
+
+out vec4 color;
+
+void main() {
+    vec4 scene = texture(source, vUv);
+    // 0 in the centre, 1 in the corners
+    float distance_from_center = length(vUv - 0.5) * sqrt(2.0);
+    float darkening = intensity * smoothstep(radius, 1.0, distance_from_center);
+
+    color = vec4(scene.rgb * (1.0 - darkening), scene.a);
+}
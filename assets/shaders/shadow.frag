@@ -0,0 +1,13 @@
+#version 140
+// cutout materials leave holes in their shadows too
+uniform float alpha_cutoff;
+uniform vec4 base_color_factor;
+uniform sampler2D base_color_map;
+
+in vec2 vUv;
+
+void main() {
+    if (texture(base_color_map, vUv).a * base_color_factor.a < alpha_cutoff) {
+        discard;
+    }
+}
@@ -0,0 +1,14 @@
+#version 140
+uniform mat4 light_matrix;
+
+in vec3 position;
+in vec2 uv;
+// per instance
+in mat4 instance_model;
+
+out vec2 vUv;
+
+void main() {
+    vUv = uv;
+    gl_Position = light_matrix * instance_model * vec4(position, 1.0);
+}
@@ -0,0 +1,16 @@
+#version 140
+uniform mat4 inverse_view_proj;
+uniform vec3 camera_position;
+uniform samplerCube environment_map;
+uniform float environment_intensity;
+
+in vec2 vNdc;
+
+out vec4 color;
+
+void main() {
+    vec4 far = inverse_view_proj * vec4(vNdc, 1.0, 1.0);
+    vec3 direction = far.xyz / far.w - camera_position;
+
+    color = vec4(textureLod(environment_map, direction, 0.0).rgb * environment_intensity, 1.0);
+}
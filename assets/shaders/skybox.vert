@@ -0,0 +1,10 @@
+#version 140
+out vec2 vNdc;
+
+void main() {
+    // one triangle covering the screen, on the far plane so anything drawn in front hides it
+    vec2 position = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2) * 2.0 - 1.0;
+
+    vNdc = position;
+    gl_Position = vec4(position, 1.0, 1.0);
+}
@@ -0,0 +1,12 @@
+#version 140
+// glyph coverage in the red channel
+uniform sampler2D glyph_atlas;
+
+in vec2 vUv;
+in vec4 vColor;
+
+out vec4 color;
+
+void main() {
+    color = vec4(vColor.rgb, vColor.a * texture(glyph_atlas, vUv).r);
+}
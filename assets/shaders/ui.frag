@@ -0,0 +1,18 @@
+#version 140
+// 0 multiplies the color with `image`, 1 uses its red channel as glyph coverage
+uniform int mode;
+uniform sampler2D image;
+
+in vec2 vUv;
+in vec4 vColor;
+
+out vec4 color;
+
+void main() {
+    vec4 texel = texture(image, vUv);
+    if (mode == 1) {
+        color = vec4(vColor.rgb, vColor.a * texel.r);
+    } else {
+        color = vColor * texel;
+    }
+}
@@ -0,0 +1,15 @@
+#version 140
+in vec2 position;
+in vec2 uv;
+in vec4 color;
+
+out vec2 vUv;
+out vec4 vColor;
+
+uniform mat4 matrix;
+
+void main() {
+    vUv = uv;
+    vColor = color;
+    gl_Position = matrix * vec4(position, 0.0, 1.0);
+}
@@ -0,0 +1,97 @@
+use crate::*;
+use serde::*;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Config {
+    pub worker_threads: usize,
+    pub window_size: (u32, u32),
+    pub window_title: String,
+
+    pub exit_on_networking_error: bool,
+
+    // writes the system schedule here after init, graphviz if it ends in `.dot`
+    #[serde(default)]
+    pub schedule_dump_path: Option<String>,
+
+    // what happens when a system fails and doesn't set its own policy
+    #[serde(default)]
+    pub system_error_policy: ErrorPolicy,
+
+    // runs without a window, closing on SIGINT/SIGTERM
+    #[serde(default)]
+    pub headless: bool,
+
+    // screenshots and recorded image sequences go here
+    #[serde(default = "default_capture_dir")]
+    pub capture_dir: String,
+
+    // frames per second of recorded image sequences
+    #[serde(default = "default_capture_fps")]
+    pub capture_fps: f64,
+
+    // recompiles shaders when their files change
+    #[serde(default = "default_shader_hot_reload")]
+    pub shader_hot_reload: bool,
+
+    // resolution and filtering of shadow maps, `Off` disables shadows
+    #[serde(default)]
+    pub shadow_quality: ShadowQuality,
+
+    // seconds `Close` systems get to finish before the game exits anyway
+    #[serde(default = "default_close_timeout")]
+    pub close_timeout: f64,
+}
+
+fn default_capture_dir() -> String {
+    "captures".to_string()
+}
+
+fn default_capture_fps() -> f64 {
+    30.0
+}
+
+fn default_shader_hot_reload() -> bool {
+    true
+}
+
+fn default_close_timeout() -> f64 {
+    5.0
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            worker_threads: 4,
+            window_size: (800, 600),
+            window_title: "Cryptid Squad".to_string(),
+            exit_on_networking_error: false,
+            schedule_dump_path: None,
+            system_error_policy: ErrorPolicy::Log,
+            headless: false,
+            capture_dir: default_capture_dir(),
+            capture_fps: default_capture_fps(),
+            shader_hot_reload: default_shader_hot_reload(),
+            shadow_quality: ShadowQuality::default(),
+            close_timeout: default_close_timeout(),
+        }
+    }
+}
+
+impl Config {
+    // writes out the default config if there isn't one at `path` yet
+    pub fn load(path: &str) -> Result<Config, Box<dyn std::error::Error>> {
+        let conf = match std::fs::read_to_string(path) {
+            Ok(conf) => conf,
+            Err(e) => {
+                println!("Error reading config file: {}", e);
+
+                let default_conf = toml::to_string(&Config::default())?;
+                std::fs::write(path, &default_conf)?;
+
+                default_conf
+            }
+        };
+
+        Ok(toml::from_str(&conf)?)
+    }
+}
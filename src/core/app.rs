@@ -0,0 +1,302 @@
+use crate::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use winit::event::{Event, VirtualKeyCode, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop, EventLoopBuilder};
+
+// seconds per update when there is no window to pace frames with vsync
+const HEADLESS_FRAME_TIME: f64 = 1.0 / 60.0;
+
+pub type WindowEventHandler = Box<dyn FnMut(&WindowEvent, &mut GameState)>;
+
+pub trait Plugin {
+    fn build(&self, app: &mut App) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+pub struct App {
+    pub conf: Arc<Config>,
+    pub runtime: Option<tokio::runtime::Runtime>,
+    pub event_loop: Option<EventLoop<()>>,
+
+    // boxed so the pointers handed to `GameState` and the fixed update loop stay valid when the
+    // app is moved into the event loop
+    pub scheduler: Box<Scheduler>,
+    pub game_state: Box<GameState>,
+
+    window_event_handlers: Vec<WindowEventHandler>,
+    fixed_update_task: Option<tokio::task::JoinHandle<()>>,
+    // set from the signal handler task, checked once per frame
+    shutdown_requested: Arc<AtomicBool>,
+    // first error returned by a plugin, reported by `run`
+    error: Option<Box<dyn std::error::Error>>,
+}
+
+impl App {
+    pub fn new() -> Result<App, Box<dyn std::error::Error>> {
+        Self::with_config(Config::load("config.toml")?)
+    }
+
+    pub fn with_config(conf: Config) -> Result<App, Box<dyn std::error::Error>> {
+        let conf = Arc::new(conf);
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(conf.worker_threads)
+            .enable_all()
+            .build()?;
+
+        let mut scheduler = Box::new(Scheduler::new(0.01));
+        scheduler.error_policy = conf.system_error_policy;
+        let mut game_state = Box::new(GameState::new(
+            &mut *scheduler as *mut Scheduler,
+            conf.clone(),
+        ));
+
+        game_state.add_resource(Time::new());
+        game_state.add_resource(Timers::new());
+        game_state.add_resource(TaskPool::new(runtime.handle().clone()));
+
+        scheduler.add_system(get_tick_timers_system(), SystemType::Update);
+        scheduler.add_system(get_apply_task_results_system(), SystemType::Update);
+
+        let event_loop = if conf.headless {
+            None
+        } else {
+            Some(EventLoopBuilder::new().build())
+        };
+
+        Ok(App {
+            conf,
+            runtime: Some(runtime),
+            event_loop,
+            scheduler,
+            game_state,
+            window_event_handlers: Vec::new(),
+            fixed_update_task: None,
+            shutdown_requested: Arc::new(AtomicBool::new(false)),
+            error: None,
+        })
+    }
+
+    pub fn add_plugin(mut self, plugin: impl Plugin) -> Self {
+        if self.error.is_none() {
+            if let Err(e) = plugin.build(&mut self) {
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+
+    pub fn add_system(&mut self, system: System, system_type: SystemType) -> &mut Self {
+        self.scheduler.add_system(system, system_type);
+        self
+    }
+
+    pub fn add_resource<T: Resource>(&mut self, resource: T) -> &mut Self {
+        self.game_state.add_resource(resource);
+        self
+    }
+
+    // handlers run on the main thread for every window event, before the app reacts to it
+    pub fn add_window_event_handler(
+        &mut self,
+        handler: impl FnMut(&WindowEvent, &mut GameState) + 'static,
+    ) -> &mut Self {
+        self.window_event_handlers.push(Box::new(handler));
+        self
+    }
+
+    pub fn run(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+
+        let rt = self.runtime.as_ref().ok_or("App has already been run")?;
+
+        rt.block_on(self.scheduler.init(&mut self.game_state));
+
+        if let Some(path) = &self.conf.schedule_dump_path {
+            if let Err(e) = self.scheduler.write_schedule(path) {
+                eprintln!("Error writing schedule to {}: {}", path, e);
+            }
+        }
+
+        let fixed_update_scheduler = unsafe { &*(&*self.scheduler as *const Scheduler) };
+        let fixed_update_future =
+            fixed_update_scheduler.loop_fixed_update(&mut *self.game_state as *mut _);
+        self.fixed_update_task = Some(rt.spawn(unsafe { SendBox::new(fixed_update_future) }));
+
+        let shutdown_requested = self.shutdown_requested.clone();
+        rt.spawn(async move {
+            wait_for_shutdown_signal().await;
+            shutdown_requested.store(true, Ordering::Relaxed);
+        });
+
+        match self.event_loop.take() {
+            Some(event_loop) => self.run_windowed(event_loop),
+            None => self.run_headless(),
+        }
+    }
+
+    fn run_windowed(mut self, event_loop: EventLoop<()>) -> ! {
+        event_loop.run(move |event, _, control_flow| {
+            *control_flow = ControlFlow::Poll;
+            match event {
+                Event::WindowEvent { event, .. } => {
+                    for handler in self.window_event_handlers.iter_mut() {
+                        handler(&event, &mut self.game_state);
+                    }
+
+                    match event {
+                        // Close the window if the exit button is pressed
+                        WindowEvent::CloseRequested => self.game_state.close(),
+                        WindowEvent::KeyboardInput { input, .. }
+                            if input.virtual_keycode == Some(VirtualKeyCode::Escape) =>
+                        {
+                            self.game_state.close()
+                        }
+                        _ => (),
+                    }
+                }
+                Event::MainEventsCleared => {
+                    if self.should_close() {
+                        self.shutdown();
+                        if let Some(error) = &self.game_state.close_error {
+                            eprintln!("Exiting after error: {}", error);
+                            std::process::exit(1);
+                        }
+                        *control_flow = ControlFlow::Exit;
+                    }
+                }
+                Event::RedrawRequested(_) => self.update(),
+                Event::RedrawEventsCleared => {
+                    if let Some(window) = self
+                        .game_state
+                        .get_resource::<RenderResource>()
+                        .and_then(|renderer| renderer.window())
+                    {
+                        window.request_redraw();
+                    }
+                }
+                _ => (),
+            }
+        });
+    }
+
+    fn run_headless(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let frame_time = Duration::from_secs_f64(HEADLESS_FRAME_TIME);
+
+        while !self.should_close() {
+            let start = Instant::now();
+            self.update();
+            std::thread::sleep(frame_time.saturating_sub(start.elapsed()));
+        }
+
+        self.shutdown();
+
+        match self.game_state.close_error.take() {
+            Some(error) => Err(error.into()),
+            None => Ok(()),
+        }
+    }
+
+    fn update(&mut self) {
+        if self.game_state.should_close {
+            return;
+        }
+
+        if let Some(rt) = &self.runtime {
+            rt.block_on(self.scheduler.update(&mut self.game_state));
+        }
+    }
+
+    fn should_close(&mut self) -> bool {
+        if self.shutdown_requested.load(Ordering::Relaxed) {
+            self.game_state.close();
+        }
+        self.game_state.should_close
+    }
+
+    // the only way the app exits, runs the `Close` systems and then stops all background tasks
+    fn shutdown(&mut self) {
+        let rt = match self.runtime.take() {
+            Some(rt) => rt,
+            None => return,
+        };
+
+        let timeout = Duration::from_secs_f64(self.conf.close_timeout);
+        let scheduler = &*self.scheduler;
+        let game_state = &mut *self.game_state;
+        let fixed_update_task = self.fixed_update_task.take();
+
+        let close = async move {
+            // waits for a running fixed step to finish so the loop is stopped between steps
+            unsafe { scheduler.force_lock().await };
+            if let Some(task) = fixed_update_task {
+                task.abort();
+            }
+            unsafe { scheduler.force_unlock().await };
+
+            scheduler.close(game_state).await;
+        };
+
+        if rt
+            .block_on(async { tokio::time::timeout(timeout, close).await })
+            .is_err()
+        {
+            eprintln!("Close systems did not finish within {:?}", timeout);
+        }
+
+        rt.shutdown_timeout(timeout);
+    }
+}
+
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = match signal(SignalKind::terminate()) {
+            Ok(terminate) => terminate,
+            Err(e) => {
+                eprintln!("Error listening for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                return;
+            }
+        };
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => (),
+            _ = terminate.recv() => (),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+struct SendBox<T>(std::pin::Pin<Box<T>>);
+
+unsafe impl<T> Send for SendBox<T> {}
+
+impl<T> SendBox<T> {
+    unsafe fn new(t: T) -> Self {
+        SendBox(Box::pin(t))
+    }
+}
+
+impl<T> futures::Future for SendBox<T>
+where
+    T: futures::Future,
+{
+    type Output = T::Output;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context,
+    ) -> std::task::Poll<Self::Output> {
+        self.0.as_mut().poll(cx)
+    }
+}
@@ -1,20 +1,45 @@
 use std::any::Any;
 use std::cell::SyncUnsafeCell;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::core::*;
 
 pub trait Resource: Any {
     fn as_any(&self) -> &dyn Any;
+    fn name(&self) -> &'static str;
+
+    // a short description of the resource's state, shown by the inspector
+    fn inspect(&self) -> Option<String> {
+        None
+    }
 }
 
 macro_rules! impl_resource {
     ($type:ty, $component_type:expr) => {
+        impl_resource!(@impl $type, $component_type;);
+    };
+    // resources that implement `Debug` are shown with it in the inspector
+    ($type:ty, $component_type:expr, debug) => {
+        impl_resource!(@impl $type, $component_type;
+            fn inspect(&self) -> Option<String> {
+                Some(format!("{:?}", self))
+            }
+        );
+    };
+    (@impl $type:ty, $component_type:expr; $($inspect:tt)*) => {
         impl crate::core::Resource for $type {
             fn as_any(&self) -> &dyn std::any::Any {
                 self
             }
+
+            fn name(&self) -> &'static str {
+                stringify!($type)
+            }
+
+            $($inspect)*
         }
         impl $type {
             pub const fn get_component_type() -> crate::core::ComponentType {
@@ -29,6 +54,8 @@ pub struct Entity {
     pub id: u32,
     pub name: String,
     pub components: Vec<Arc<SyncUnsafeCell<ComponentStruct>>>,
+    // despawned entities keep their slot so ids stay indices into `GameState::entities`
+    pub alive: bool,
 }
 
 impl Entity {
@@ -37,6 +64,7 @@ impl Entity {
             id,
             name,
             components: Vec::new(),
+            alive: true,
         }
     }
 
@@ -124,29 +152,97 @@ pub enum SystemType {
     Close,
 }
 
+pub type SystemResult = Result<(), Box<dyn std::error::Error>>;
+
+// what the scheduler does when a system returns an error or panics
+#[derive(Debug, Default, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum ErrorPolicy {
+    // print the error and keep running the system
+    #[default]
+    Log,
+    // print the error and stop running the system
+    Disable,
+    // print the error and shut the game down
+    Close,
+}
+
 pub struct System {
+    pub name: &'static str,
     pub args: Vec<ComponentType>,
+    // type names of `args`, only used for debugging output
+    pub arg_names: Vec<&'static str>,
     pub system: Box<
-        dyn Fn(*mut GameState, f64, f64) -> Pin<Box<dyn futures::Future<Output = ()>>>
+        dyn Fn(*mut GameState, f64, f64) -> Pin<Box<dyn futures::Future<Output = SystemResult>>>
             + Send
             + Sync,
     >,
+
+    // falls back to the scheduler's policy when `None`
+    pub error_policy: Option<ErrorPolicy>,
+    pub enabled: AtomicBool,
+    // nanoseconds the system took the last time it ran
+    pub last_duration: AtomicU64,
 }
 
+impl System {
+    pub fn with_error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = Some(error_policy);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn last_duration(&self) -> Duration {
+        Duration::from_nanos(self.last_duration.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn set_last_duration(&self, duration: Duration) {
+        self.last_duration
+            .store(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug)]
+pub struct MissingResource(pub &'static str);
+
+impl std::fmt::Display for MissingResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Missing resource {}", self.0)
+    }
+}
+
+impl std::error::Error for MissingResource {}
+
 macro_rules! create_system {
     ($sys: ident, $getter: ident) => {
         pub fn $getter() -> System {
             System {
+                name: stringify!($sys),
                 system: force_boxed!($sys),
                 args: Vec::new(),
+                arg_names: Vec::new(),
+                error_policy: None,
+                enabled: std::sync::atomic::AtomicBool::new(true),
+                last_duration: std::sync::atomic::AtomicU64::new(0),
             }
         }
     };
     ($sys: ident, $getter: ident; uses $($t:ty),+) => {
         pub fn $getter() -> System {
             System {
+                name: stringify!($sys),
                 system: force_boxed!($sys),
                 args: vec![$(<$t>::get_component_type()),+],
+                arg_names: vec![$(stringify!($t)),+],
+                error_policy: None,
+                enabled: std::sync::atomic::AtomicBool::new(true),
+                last_duration: std::sync::atomic::AtomicU64::new(0),
             }
         }
     };
@@ -4,7 +4,7 @@ use std::cell::SyncUnsafeCell;
 use std::sync::Arc;
 
 // increase this every time you add a new component type
-const COMPONENT_TYPES: usize = 8;
+const COMPONENT_TYPES: usize = 24;
 
 pub struct GameState {
     pub entities: Vec<Arc<SyncUnsafeCell<Entity>>>,
@@ -14,9 +14,11 @@ pub struct GameState {
     pub scheduler: *mut Scheduler,
 
     pub next_entity_id: u32,
-    pub conf: &'static Config,
+    pub conf: Arc<Config>,
 
     pub should_close: bool,
+    // first error that closed the game, makes the process exit unsuccessfully
+    pub close_error: Option<String>,
 }
 
 impl GameState {
@@ -24,6 +26,11 @@ impl GameState {
         self.should_close = true;
     }
 
+    pub fn close_with_error(&mut self, error: String) {
+        self.close_error.get_or_insert(error);
+        self.close();
+    }
+
     pub fn get_scheduler<'a>(&'a self) -> &'a Scheduler {
         unsafe { &*self.scheduler }
     }
@@ -36,7 +43,7 @@ impl GameState {
         COMPONENT_TYPES
     }
 
-    pub fn new(scheduler: *mut Scheduler, conf: &'static Config) -> GameState {
+    pub fn new(scheduler: *mut Scheduler, conf: Arc<Config>) -> GameState {
         GameState {
             entities: Vec::new(),
             components: vec![Vec::new(); COMPONENT_TYPES],
@@ -46,6 +53,7 @@ impl GameState {
 
             conf,
             should_close: false,
+            close_error: None,
         }
     }
 
@@ -59,6 +67,20 @@ impl GameState {
         unsafe { &mut *rc.get() }
     }
 
+    // removes the entity's components, the entity itself stays behind marked as not alive
+    pub fn despawn_entity(&mut self, id: usize) {
+        let Some(entity) = self.get_entity_mut(id) else {
+            return;
+        };
+        let components = std::mem::take(&mut entity.components);
+        entity.alive = false;
+
+        for component in components {
+            let component_type = unsafe { &*component.get() }.component_type;
+            self.components[component_type].retain(|other| !Arc::ptr_eq(other, &component));
+        }
+    }
+
     pub fn get_entity<'a>(&'a self, id: usize) -> Option<&'a mut Entity> {
         if id >= self.entities.len() {
             return None;
@@ -145,6 +167,47 @@ impl GameState {
         None
     }
 
+    // like `get_resource` but with an error that can be returned from a system with `?`
+    pub fn require_resource<T: Resource>(&self) -> Result<&T, MissingResource> {
+        self.get_resource::<T>()
+            .ok_or(MissingResource(std::any::type_name::<T>()))
+    }
+
+    pub fn require_resource_mut<T: Resource>(&mut self) -> Result<&mut T, MissingResource> {
+        self.get_resource_mut::<T>()
+            .ok_or(MissingResource(std::any::type_name::<T>()))
+    }
+
+    pub fn remove_resource<T: Resource>(&mut self) -> Option<T> {
+        let index = self
+            .resources
+            .iter()
+            .position(|resource| resource.as_ref().as_any().is::<T>())?;
+
+        let resource: Box<dyn Any> = self.resources.remove(index);
+        resource.downcast::<T>().ok().map(|resource| *resource)
+    }
+
+    // takes the resource out so it can be changed alongside the rest of the game state, it's put
+    // back afterwards even if `f` panics
+    pub fn with_resource_removed<T: Resource, R>(
+        &mut self,
+        f: impl FnOnce(&mut T, &mut GameState) -> R,
+    ) -> Result<R, MissingResource> {
+        let mut resource = self
+            .remove_resource::<T>()
+            .ok_or(MissingResource(std::any::type_name::<T>()))?;
+
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(&mut resource, self)));
+        self.add_resource(resource);
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+
     pub fn get_resource_mut<'a, T: Resource>(&'a mut self) -> Option<&'a mut T> {
         for resource in &mut self.resources {
             if let Some(r) = (resource.as_mut() as &mut dyn std::any::Any).downcast_mut::<T>() {
@@ -154,3 +217,44 @@ impl GameState {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn require_resource_names_the_missing_resource() {
+        let mut game_state = GameState::new(std::ptr::null_mut(), Arc::new(Config::default()));
+
+        let error = game_state.require_resource::<Time>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("Missing resource {}", std::any::type_name::<Time>())
+        );
+        assert!(game_state.require_resource_mut::<Time>().is_err());
+
+        game_state.add_resource(Time::new_manual());
+        assert!(game_state.require_resource::<Time>().is_ok());
+        assert!(game_state.require_resource_mut::<Time>().is_ok());
+    }
+
+    #[test]
+    fn removed_resources_are_put_back_after_a_panic() {
+        let mut game_state = GameState::new(std::ptr::null_mut(), Arc::new(Config::default()));
+        assert!(game_state
+            .with_resource_removed(|_: &mut Ui, _| ())
+            .is_err());
+
+        game_state.add_resource(Ui::new());
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            game_state.with_resource_removed(|ui: &mut Ui, game_state| {
+                assert!(game_state.get_resource::<Ui>().is_none());
+                ui.hidden = true;
+                panic!("system failed");
+            })
+        }));
+
+        assert!(panicked.is_err());
+        assert!(game_state.require_resource::<Ui>().unwrap().hidden);
+    }
+}
@@ -9,3 +9,6 @@ pub use ecs::*;
 
 pub mod render;
 pub use render::*;
+
+pub mod app;
+pub use app::*;
@@ -0,0 +1,188 @@
+use crate::*;
+use glium::backend::Facade;
+use std::ops::Range;
+use std::rc::Rc;
+
+// per instance vertex data, shaders read the object's model matrix from `instance_model`
+#[derive(Copy, Clone, Debug)]
+pub struct Instance {
+    pub instance_model: [[f32; 4]; 4],
+}
+
+glium::implement_vertex!(Instance, instance_model);
+
+// objects sharing a model and an equal material, drawn with a single instanced draw call
+#[derive(Debug)]
+pub struct Batch<'a> {
+    pub model: &'a Model,
+    pub material: &'a Material,
+    // this batch's part of `Batches::instances`
+    pub instances: Range<usize>,
+    // the start of `instances` that is inside the camera's frustum, the rest is only drawn into
+    // shadow maps
+    pub visible: Range<usize>,
+}
+
+#[derive(Debug)]
+pub struct Batches<'a> {
+    pub batches: Vec<Batch<'a>>,
+    // every batch's instances back to back, `None` when there is nothing to draw
+    pub instances: Option<glium::VertexBuffer<Instance>>,
+}
+
+// instances of one batch while they are being gathered
+struct Group<'a> {
+    model: &'a Model,
+    material: &'a Material,
+    visible: Vec<Instance>,
+    culled: Vec<Instance>,
+    // squared, from the camera to the object, only used for transparent objects
+    distance: f32,
+}
+
+impl<'a> Batches<'a> {
+    // opaque objects are grouped in the order their first instance was found, so the draw order
+    // stays the same between frames, followed by every transparent object on its own from back to
+    // front
+    pub fn new(
+        game_state: &'a GameState,
+        facade: &dyn Facade,
+        camera: &Camera,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let frustum = camera.get_frustum();
+        let camera_position = camera.get_world_position();
+
+        let mut groups: Vec<Group> = Vec::new();
+        let mut transparent: Vec<Group> = Vec::new();
+
+        for entity in game_state
+            .get_entities_with::<RenderObject>(RenderObject::get_component_type())
+            .into_iter()
+        {
+            let (Some(transform), Some(model), Some(material)) = (
+                entity.get_component::<Transform>(Transform::get_component_type()),
+                entity.get_component::<Model>(Model::get_component_type()),
+                entity.get_component::<Material>(Material::get_component_type()),
+            ) else {
+                continue;
+            };
+
+            let matrix = transform.get_model();
+            let bounds = model.bounds.transformed(&matrix);
+            let visible = frustum.intersects_bounds(&bounds);
+            let instance = Instance {
+                instance_model: matrix.0,
+            };
+
+            let group = if material.blend_mode.is_transparent() {
+                let center = bounds.center();
+                let offset = [
+                    center[0] - camera_position[0],
+                    center[1] - camera_position[1],
+                    center[2] - camera_position[2],
+                ];
+                transparent.push(Group {
+                    model,
+                    material,
+                    visible: Vec::new(),
+                    culled: Vec::new(),
+                    distance: dot(offset, offset),
+                });
+                transparent.last_mut().unwrap()
+            } else {
+                let index = groups.iter().position(|group| {
+                    Rc::ptr_eq(&group.model.vertices, &model.vertices)
+                        && Rc::ptr_eq(&group.model.indices, &model.indices)
+                        && group.material == material
+                });
+                match index {
+                    Some(index) => &mut groups[index],
+                    None => {
+                        groups.push(Group {
+                            model,
+                            material,
+                            visible: Vec::new(),
+                            culled: Vec::new(),
+                            distance: 0.0,
+                        });
+                        groups.last_mut().unwrap()
+                    }
+                }
+            };
+
+            if visible {
+                group.visible.push(instance);
+            } else {
+                group.culled.push(instance);
+            }
+        }
+
+        transparent.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+        groups.extend(transparent);
+
+        let mut batches = Vec::with_capacity(groups.len());
+        let mut instances = Vec::new();
+        for group in groups {
+            let start = instances.len();
+            instances.extend(group.visible);
+            let visible_end = instances.len();
+            instances.extend(group.culled);
+
+            batches.push(Batch {
+                model: group.model,
+                material: group.material,
+                instances: start..instances.len(),
+                visible: start..visible_end,
+            });
+        }
+
+        let instances = if instances.is_empty() {
+            None
+        } else {
+            Some(glium::VertexBuffer::dynamic(facade, &instances)?)
+        };
+
+        Ok(Batches { batches, instances })
+    }
+
+    // one instanced draw of `batch`'s model, `instances` is either its `instances` or `visible`
+    pub fn draw<S: glium::Surface, U: glium::uniforms::Uniforms>(
+        &self,
+        batch: &Batch<'a>,
+        instances: Range<usize>,
+        surface: &mut S,
+        program: &glium::Program,
+        uniforms: &U,
+        params: &glium::DrawParameters,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let instances = self
+            .instances
+            .as_ref()
+            .and_then(|buffer| buffer.slice(instances))
+            .ok_or("Batch instances out of range")?;
+        let instances = instances
+            .per_instance()
+            .map_err(|_| "Instanced drawing is not supported")?;
+
+        surface.draw(
+            (&*batch.model.vertices, instances),
+            &*batch.model.indices,
+            program,
+            uniforms,
+            params,
+        )?;
+        Ok(())
+    }
+}
+
+// counts from the last rendered frame
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderStats {
+    pub draw_calls: usize,
+    pub shadow_draw_calls: usize,
+    pub batches: usize,
+    // render objects inside and outside of the camera's frustum
+    pub drawn: usize,
+    pub culled: usize,
+    pub post_passes: usize,
+}
@@ -0,0 +1,185 @@
+use crate::*;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use winit::event::{ElementState, VirtualKeyCode, WindowEvent};
+
+const SCREENSHOT_KEY: VirtualKeyCode = VirtualKeyCode::F12;
+const RECORD_KEY: VirtualKeyCode = VirtualKeyCode::F11;
+
+pub type CaptureCallback = Box<dyn FnOnce(&mut GameState, image::RgbaImage)>;
+
+pub enum CaptureRequest {
+    Save(PathBuf),
+    Callback(CaptureCallback),
+}
+
+impl std::fmt::Debug for CaptureRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CaptureRequest::Save(path) => write!(f, "Save({:?})", path),
+            CaptureRequest::Callback(_) => write!(f, "Callback"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ImageSequence {
+    pub dir: PathBuf,
+    pub frame_time: f64,
+    pub next_frame: u32,
+}
+
+impl RenderResource<'_> {
+    // saves the next finished frame as a png
+    pub fn capture_frame(&mut self, path: impl Into<PathBuf>) {
+        self.captures.push(CaptureRequest::Save(path.into()));
+    }
+
+    pub fn capture_frame_with(
+        &mut self,
+        callback: impl FnOnce(&mut GameState, image::RgbaImage) + 'static,
+    ) {
+        self.captures
+            .push(CaptureRequest::Callback(Box::new(callback)));
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.sequence.is_some()
+    }
+}
+
+// saves every frame as `dir/frame_00000.png` and so on, the clock steps by exactly `1 / fps` per
+// frame so the sequence plays back at the right speed no matter how slowly it renders
+pub fn start_image_sequence(
+    game_state: &mut GameState,
+    dir: impl Into<PathBuf>,
+    fps: f64,
+) -> SystemResult {
+    if !fps.is_finite() || fps <= 0.0 {
+        return Err(format!("Image sequence fps has to be positive, got {}", fps).into());
+    }
+
+    let dir = dir.into();
+    std::fs::create_dir_all(&dir)?;
+
+    game_state
+        .require_resource_mut::<Time>()?
+        .use_manual_clock();
+    game_state
+        .require_resource_mut::<RenderResource>()?
+        .sequence = Some(ImageSequence {
+        dir,
+        frame_time: 1.0 / fps,
+        next_frame: 0,
+    });
+
+    Ok(())
+}
+
+pub fn stop_image_sequence(game_state: &mut GameState) -> Option<ImageSequence> {
+    if let Some(time) = game_state.get_resource_mut::<Time>() {
+        time.use_real_clock();
+    }
+
+    game_state
+        .get_resource_mut::<RenderResource>()?
+        .sequence
+        .take()
+}
+
+// called by `render` once the frame is finished
+pub(crate) fn process_captures(game_state: &mut GameState) -> SystemResult {
+    let renderer = game_state.require_resource_mut::<RenderResource>()?;
+    if renderer.captures.is_empty() && renderer.sequence.is_none() {
+        return Ok(());
+    }
+
+    let image = renderer.read_pixels()?;
+    let requests = std::mem::take(&mut renderer.captures);
+    let sequence_frame = renderer.sequence.as_mut().map(|sequence| {
+        let path = sequence
+            .dir
+            .join(format!("frame_{:05}.png", sequence.next_frame));
+        sequence.next_frame += 1;
+        (path, sequence.frame_time)
+    });
+
+    if let Some((path, frame_time)) = sequence_frame {
+        save_in_background(game_state, image.clone(), path)?;
+        game_state
+            .require_resource_mut::<Time>()?
+            .advance(frame_time);
+    }
+
+    for request in requests {
+        match request {
+            CaptureRequest::Save(path) => save_in_background(game_state, image.clone(), path)?,
+            CaptureRequest::Callback(callback) => callback(game_state, image.clone()),
+        }
+    }
+
+    Ok(())
+}
+
+// encoding a png takes long enough to cause a visible hitch on the main thread
+fn save_in_background(
+    game_state: &GameState,
+    image: image::RgbaImage,
+    path: PathBuf,
+) -> SystemResult {
+    game_state
+        .require_resource::<TaskPool>()?
+        .spawn_blocking(move || {
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            if let Err(e) = image.save(&path) {
+                eprintln!("Error saving {}: {}", path.display(), e);
+            }
+        });
+
+    Ok(())
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// F12 saves a screenshot, F11 starts and stops recording an image sequence
+pub(crate) fn handle_capture_keys(event: &WindowEvent, game_state: &mut GameState) {
+    let key = match event {
+        WindowEvent::KeyboardInput { input, .. } if input.state == ElementState::Pressed => {
+            input.virtual_keycode
+        }
+        _ => return,
+    };
+
+    let capture_dir = PathBuf::from(&game_state.conf.capture_dir);
+
+    if key == Some(SCREENSHOT_KEY) {
+        let path = capture_dir.join(format!("screenshot_{}.png", timestamp()));
+        if let Some(renderer) = game_state.get_resource_mut::<RenderResource>() {
+            println!("Saving screenshot to {}", path.display());
+            renderer.capture_frame(path);
+        }
+    } else if key == Some(RECORD_KEY) {
+        if let Some(sequence) = stop_image_sequence(game_state) {
+            println!(
+                "Recorded {} frames to {}",
+                sequence.next_frame,
+                sequence.dir.display()
+            );
+            return;
+        }
+
+        let dir = capture_dir.join(format!("recording_{}", timestamp()));
+        let fps = game_state.conf.capture_fps;
+        match start_image_sequence(game_state, &dir, fps) {
+            Ok(()) => println!("Recording to {}", dir.display()),
+            Err(e) => eprintln!("Error starting recording: {}", e),
+        }
+    }
+}
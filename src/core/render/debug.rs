@@ -0,0 +1,369 @@
+use crate::*;
+use glium::Surface;
+use std::f32::consts::PI;
+
+// draws debug lines, colored by vertex
+pub const DEBUG_SHADER: &str = "debug";
+
+// line segments in every circle of a debug sphere
+const CIRCLE_SEGMENTS: usize = 24;
+// half the size of the cross marking a label
+const LABEL_MARKER_SIZE: f32 = 0.05;
+
+#[derive(Copy, Clone, Debug)]
+pub struct DebugVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+glium::implement_vertex!(DebugVertex, position, color);
+
+#[derive(Debug, Clone)]
+pub struct DebugLine {
+    pub start: [f32; 3],
+    pub end: [f32; 3],
+    pub color: [f32; 4],
+    // hidden behind the scene when set, drawn over everything otherwise
+    pub depth_test: bool,
+    // seconds left to draw it for, it is always drawn for at least one frame
+    pub remaining: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugLabel {
+    pub position: [f32; 3],
+    pub text: String,
+    pub color: [f32; 4],
+    pub depth_test: bool,
+    pub remaining: f64,
+}
+
+// component type 20
+// shapes queued from any system, including `FixedUpdate` ones, and drawn over the scene. shapes
+// are only drawn on the next frame unless given a duration, shapes from `FixedUpdate` should last
+// at least a fixed step so they don't flicker
+#[derive(Debug)]
+pub struct DebugDraw {
+    pub enabled: bool,
+    pub lines: Vec<DebugLine>,
+    pub labels: Vec<DebugLabel>,
+}
+impl_resource!(DebugDraw, 20);
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the lines and labels of the shape that was just queued, to change how it is drawn
+pub struct DebugShape<'a> {
+    lines: &'a mut [DebugLine],
+    labels: &'a mut [DebugLabel],
+}
+
+impl DebugShape<'_> {
+    // keeps the shape for `seconds` of real time
+    pub fn duration(self, seconds: f64) -> Self {
+        self.lines
+            .iter_mut()
+            .for_each(|line| line.remaining = seconds);
+        self.labels
+            .iter_mut()
+            .for_each(|label| label.remaining = seconds);
+        self
+    }
+
+    // draws the shape over everything instead of hiding it behind the scene
+    pub fn on_top(self) -> Self {
+        self.lines
+            .iter_mut()
+            .for_each(|line| line.depth_test = false);
+        self.labels
+            .iter_mut()
+            .for_each(|label| label.depth_test = false);
+        self
+    }
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        DebugDraw {
+            enabled: true,
+            lines: Vec::new(),
+            labels: Vec::new(),
+        }
+    }
+
+    pub fn line(&mut self, start: [f32; 3], end: [f32; 3], color: [f32; 4]) -> DebugShape<'_> {
+        self.lines(&[(start, end)], color)
+    }
+
+    pub fn lines(&mut self, segments: &[([f32; 3], [f32; 3])], color: [f32; 4]) -> DebugShape<'_> {
+        let first = self.lines.len();
+        self.lines
+            .extend(segments.iter().map(|&(start, end)| DebugLine {
+                start,
+                end,
+                color,
+                depth_test: true,
+                remaining: 0.0,
+            }));
+
+        DebugShape {
+            lines: &mut self.lines[first..],
+            labels: &mut [],
+        }
+    }
+
+    pub fn aabb(&mut self, bounds: &Bounds, color: [f32; 4]) -> DebugShape<'_> {
+        let c = bounds.corners();
+        // corners are numbered by which of x, y and z are at their max, as bits
+        let edges = [
+            (0, 1),
+            (2, 3),
+            (4, 5),
+            (6, 7),
+            (0, 2),
+            (1, 3),
+            (4, 6),
+            (5, 7),
+            (0, 4),
+            (1, 5),
+            (2, 6),
+            (3, 7),
+        ];
+
+        self.lines(&edges.map(|(a, b)| (c[a], c[b])), color)
+    }
+
+    // a circle around each axis
+    pub fn sphere(&mut self, center: [f32; 3], radius: f32, color: [f32; 4]) -> DebugShape<'_> {
+        let point = |axis: usize, angle: f32| {
+            let (sin, cos) = angle.sin_cos();
+            let mut offset = [0.0; 3];
+            offset[(axis + 1) % 3] = cos * radius;
+            offset[(axis + 2) % 3] = sin * radius;
+            [
+                center[0] + offset[0],
+                center[1] + offset[1],
+                center[2] + offset[2],
+            ]
+        };
+
+        let segments: Vec<([f32; 3], [f32; 3])> = (0..3)
+            .flat_map(|axis| {
+                (0..CIRCLE_SEGMENTS).map(move |i| {
+                    let step = 2.0 * PI / CIRCLE_SEGMENTS as f32;
+                    (
+                        point(axis, i as f32 * step),
+                        point(axis, (i + 1) as f32 * step),
+                    )
+                })
+            })
+            .collect();
+
+        self.lines(&segments, color)
+    }
+
+    // red, green and blue lines along the x, y and z axes of `matrix`, like a `Transform`'s model
+    // matrix
+    pub fn axes(&mut self, matrix: &Matrix4, length: f32) -> DebugShape<'_> {
+        let origin = matrix.transform_point([0.0; 3]);
+        let first = self.lines.len();
+
+        for (axis, color) in [
+            [1.0, 0.0, 0.0, 1.0],
+            [0.0, 1.0, 0.0, 1.0],
+            [0.0, 0.0, 1.0, 1.0],
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut end = [0.0; 3];
+            end[axis] = length;
+            self.line(origin, matrix.transform_point(end), color);
+        }
+
+        DebugShape {
+            lines: &mut self.lines[first..],
+            labels: &mut [],
+        }
+    }
+
+    // a line with a head at `end`
+    pub fn arrow(&mut self, start: [f32; 3], end: [f32; 3], color: [f32; 4]) -> DebugShape<'_> {
+        let direction = [end[0] - start[0], end[1] - start[1], end[2] - start[2]];
+        let length = dot(direction, direction).sqrt();
+        if length <= f32::EPSILON {
+            return self.line(start, end, color);
+        }
+
+        let forward = normalize(direction);
+        let up = if forward[1].abs() > 0.99 {
+            [1.0, 0.0, 0.0]
+        } else {
+            [0.0, 1.0, 0.0]
+        };
+        let side = normalize(cross(forward, up));
+        let up = cross(side, forward);
+
+        let head = length * 0.2;
+        let back = |offset: [f32; 3]| {
+            [
+                end[0] - forward[0] * head + offset[0] * head * 0.5,
+                end[1] - forward[1] * head + offset[1] * head * 0.5,
+                end[2] - forward[2] * head + offset[2] * head * 0.5,
+            ]
+        };
+        let negate = |v: [f32; 3]| [-v[0], -v[1], -v[2]];
+
+        self.lines(
+            &[
+                (start, end),
+                (end, back(side)),
+                (end, back(negate(side))),
+                (end, back(up)),
+                (end, back(negate(up))),
+            ],
+            color,
+        )
+    }
+
+    // text at a point in the world
+    pub fn text(&mut self, position: [f32; 3], text: &str, color: [f32; 4]) -> DebugShape<'_> {
+        self.labels.push(DebugLabel {
+            position,
+            text: text.to_string(),
+            color,
+            depth_test: true,
+            remaining: 0.0,
+        });
+
+        let first = self.labels.len() - 1;
+        DebugShape {
+            lines: &mut [],
+            labels: &mut self.labels[first..],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.labels.clear();
+    }
+
+    // called after every frame with the real time it took, drops the shapes whose time is up
+    pub fn end_frame(&mut self, dt: f64) {
+        self.lines.retain_mut(|line| {
+            line.remaining -= dt;
+            line.remaining > 0.0
+        });
+        self.labels.retain_mut(|label| {
+            label.remaining -= dt;
+            label.remaining > 0.0
+        });
+    }
+
+    // every line as vertex pairs, depth tested lines first. returns the vertices and how many of
+    // them are depth tested
+    fn vertices(&self) -> (Vec<DebugVertex>, usize) {
+        // labels are marked with a small cross at their position, their text starts next to it
+        let markers = self.labels.iter().flat_map(|label| {
+            (0..3).map(|axis| {
+                let (mut start, mut end) = (label.position, label.position);
+                start[axis] -= LABEL_MARKER_SIZE;
+                end[axis] += LABEL_MARKER_SIZE;
+                (start, end, label.color, label.depth_test)
+            })
+        });
+        let segments = self
+            .lines
+            .iter()
+            .map(|line| (line.start, line.end, line.color, line.depth_test))
+            .chain(markers);
+
+        let (tested, on_top): (Vec<_>, Vec<_>) = segments.partition(|segment| segment.3);
+        let depth_tested = tested.len() * 2;
+
+        let vertices = tested
+            .into_iter()
+            .chain(on_top)
+            .flat_map(|(start, end, color, _)| {
+                [
+                    DebugVertex {
+                        position: start,
+                        color,
+                    },
+                    DebugVertex {
+                        position: end,
+                        color,
+                    },
+                ]
+            })
+            .collect();
+
+        (vertices, depth_tested)
+    }
+
+    // draws the queued shapes, returning the number of draw calls made
+    pub fn draw<S: Surface>(
+        &self,
+        facade: &dyn glium::backend::Facade,
+        program: &glium::Program,
+        camera: &Camera,
+        frame: &mut S,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        if !self.enabled {
+            return Ok(0);
+        }
+
+        let (vertices, depth_tested) = self.vertices();
+        if vertices.is_empty() {
+            return Ok(0);
+        }
+
+        let buffer = glium::VertexBuffer::new(facade, &vertices)?;
+        let uniforms = glium::uniform! {
+            view: camera.get_view(),
+            proj: camera.get_proj(),
+        };
+
+        let mut draw_calls = 0;
+        for (range, test) in [
+            (
+                0..depth_tested,
+                glium::draw_parameters::DepthTest::IfLessOrEqual,
+            ),
+            (
+                depth_tested..vertices.len(),
+                glium::draw_parameters::DepthTest::Overwrite,
+            ),
+        ] {
+            if range.is_empty() {
+                continue;
+            }
+
+            let params = glium::DrawParameters {
+                depth: glium::Depth {
+                    test,
+                    write: false,
+                    ..Default::default()
+                },
+                blend: glium::Blend::alpha_blending(),
+                ..Default::default()
+            };
+            let vertices = buffer.slice(range).ok_or("Debug vertices out of range")?;
+
+            frame.draw(
+                vertices,
+                glium::index::NoIndices(glium::index::PrimitiveType::LinesList),
+                program,
+                &uniforms,
+                &params,
+            )?;
+            draw_calls += 1;
+        }
+
+        Ok(draw_calls)
+    }
+}
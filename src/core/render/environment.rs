@@ -0,0 +1,414 @@
+use crate::*;
+use glium::backend::Facade;
+use glium::texture::{CubeLayer, Cubemap};
+use glium::Surface;
+use std::borrow::Cow;
+use std::f32::consts::PI;
+
+// draws the environment behind everything else
+pub const SKYBOX_SHADER: &str = "skybox";
+
+// diffuse light changes slowly with direction, so its cubemap can be tiny
+const IRRADIANCE_SIZE: usize = 16;
+// the mip level size that is integrated for each irradiance texel
+const IRRADIANCE_SAMPLE_SIZE: usize = 8;
+
+// limits the skybox resolution picked for equirectangular images
+const MAX_FACE_SIZE: usize = 1024;
+
+// in the order of OpenGL's cubemap faces, which is also the order `load_cubemap` takes them in
+const LAYERS: [CubeLayer; 6] = [
+    CubeLayer::PositiveX,
+    CubeLayer::NegativeX,
+    CubeLayer::PositiveY,
+    CubeLayer::NegativeY,
+    CubeLayer::PositiveZ,
+    CubeLayer::NegativeZ,
+];
+
+// square images of linear colors, one per face
+type Faces = [Vec<[f32; 3]>; 6];
+
+// component type 19
+// the sky drawn behind the scene, also lighting it through reflections and diffuse light
+#[derive(Debug)]
+pub struct Environment {
+    // every mip level is half as sharp, rougher surfaces reflect blurrier levels
+    pub skybox: Cubemap,
+    // the light reaching a surface facing each direction, divided by pi
+    pub irradiance: Cubemap,
+    pub intensity: f32,
+}
+impl_resource!(Environment, 19);
+
+impl Environment {
+    // a panorama covering 360 degrees horizontally and 180 vertically, HDR formats are read as
+    // linear and everything else as sRGB
+    pub fn load_equirectangular(
+        path: &str,
+        facade: &dyn Facade,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let (width, height, pixels) = linear_pixels(image::open(path)?)?;
+        let size = (width / 4).next_power_of_two().min(MAX_FACE_SIZE);
+
+        let faces = std::array::from_fn(|face| {
+            face_directions(face, size)
+                .map(|direction| {
+                    // +y is up and -z is the middle of the image
+                    let u = 0.5 + direction[0].atan2(-direction[2]) / (2.0 * PI);
+                    let v = direction[1].clamp(-1.0, 1.0).acos() / PI;
+
+                    let x = ((u * width as f32) as usize).min(width - 1);
+                    let y = ((v * height as f32) as usize).min(height - 1);
+                    pixels[y * width + x]
+                })
+                .collect()
+        });
+
+        Self::from_faces(facade, size, faces)
+    }
+
+    // six square images in the order +x, -x, +y, -y, +z, -z
+    pub fn load_cubemap(
+        paths: [&str; 6],
+        facade: &dyn Facade,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut size = None;
+        let mut faces: Faces = Default::default();
+
+        for (face, path) in faces.iter_mut().zip(paths) {
+            let (width, height, pixels) = linear_pixels(image::open(path)?)?;
+            if width != height || size.is_some_and(|size| size != width) {
+                return Err(
+                    format!("Cubemap face {} isn't square or has a different size", path).into(),
+                );
+            }
+            size = Some(width);
+            *face = pixels;
+        }
+
+        Self::from_faces(facade, size.unwrap_or(1), faces)
+    }
+
+    // the same light from every direction
+    pub fn from_color(
+        facade: &dyn Facade,
+        color: [f32; 3],
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_faces(facade, 1, std::array::from_fn(|_| vec![color]))
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    // the highest mip level of `skybox`, used for fully rough reflections
+    pub fn max_lod(&self) -> f32 {
+        self.skybox.get_mipmap_levels().saturating_sub(1) as f32
+    }
+
+    fn from_faces(
+        facade: &dyn Facade,
+        size: usize,
+        faces: Faces,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let levels = mip_chain(faces, size);
+
+        let sample_level = (size / IRRADIANCE_SAMPLE_SIZE.min(size)).trailing_zeros() as usize;
+        let irradiance = irradiance(&levels[sample_level], size >> sample_level);
+
+        Ok(Environment {
+            skybox: upload(facade, size, &levels)?,
+            irradiance: upload(facade, IRRADIANCE_SIZE, &[irradiance])?,
+            intensity: 1.0,
+        })
+    }
+
+    pub fn sampler(&self) -> glium::uniforms::SamplerBehavior {
+        glium::uniforms::SamplerBehavior {
+            magnify_filter: glium::uniforms::MagnifySamplerFilter::Linear,
+            minify_filter: glium::uniforms::MinifySamplerFilter::LinearMipmapLinear,
+            wrap_function: (
+                glium::uniforms::SamplerWrapFunction::Clamp,
+                glium::uniforms::SamplerWrapFunction::Clamp,
+                glium::uniforms::SamplerWrapFunction::Clamp,
+            ),
+            ..Default::default()
+        }
+    }
+
+    // drawn after opaque objects, wherever they left the depth buffer clear
+    pub fn draw_skybox<S: Surface>(
+        &self,
+        program: &glium::Program,
+        camera: &Camera,
+        frame: &mut S,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // matrices multiply in reverse, this is `inverse(proj * view)`
+        let inverse_view_proj = (Matrix4(camera.get_view()) * Matrix4(camera.get_proj()))
+            .inverse()
+            .ok_or("Camera matrix can't be inverted")?;
+
+        let uniforms = glium::uniform! {
+            inverse_view_proj: inverse_view_proj.0,
+            camera_position: camera.get_world_position(),
+            environment_map: glium::uniforms::Sampler(&self.skybox, self.sampler()),
+            environment_intensity: self.intensity,
+        };
+        let params = glium::DrawParameters {
+            depth: glium::Depth {
+                test: glium::draw_parameters::DepthTest::IfLessOrEqual,
+                write: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        draw_fullscreen(frame, program, &uniforms, &params)
+    }
+}
+
+fn linear_pixels(
+    image: image::DynamicImage,
+) -> Result<(usize, usize, Vec<[f32; 3]>), Box<dyn std::error::Error>> {
+    if image.width() == 0 || image.height() == 0 {
+        return Err("Environment image is empty".into());
+    }
+
+    let srgb = !matches!(
+        image,
+        image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_)
+    );
+    let image = image.into_rgb32f();
+    let to_linear = |c: f32| {
+        if !srgb {
+            c
+        } else if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+
+    let pixels = image.pixels().map(|pixel| pixel.0.map(to_linear)).collect();
+    Ok((image.width() as usize, image.height() as usize, pixels))
+}
+
+// the direction through the centre of every texel of a face, row by row
+fn face_directions(face: usize, size: usize) -> impl Iterator<Item = [f32; 3]> {
+    (0..size * size).map(move |i| {
+        let s = 2.0 * ((i % size) as f32 + 0.5) / size as f32 - 1.0;
+        let t = 2.0 * ((i / size) as f32 + 0.5) / size as f32 - 1.0;
+
+        // from the cubemap face selection table in the OpenGL spec
+        normalize(match face {
+            0 => [1.0, -t, -s],
+            1 => [-1.0, -t, s],
+            2 => [s, 1.0, t],
+            3 => [s, -1.0, -t],
+            4 => [s, -t, 1.0],
+            _ => [-s, -t, -1.0],
+        })
+    })
+}
+
+// `faces` followed by every smaller level down to 1x1
+fn mip_chain(faces: Faces, size: usize) -> Vec<Faces> {
+    let mut levels = vec![faces];
+    let mut level_size = size;
+    while level_size > 1 {
+        levels.push(downsample(levels.last().unwrap(), level_size));
+        level_size /= 2;
+    }
+    levels
+}
+
+// averages every 2x2 block of texels
+fn downsample(faces: &Faces, size: usize) -> Faces {
+    let half = (size / 2).max(1);
+    std::array::from_fn(|face| {
+        (0..half * half)
+            .map(|i| {
+                let (x, y) = ((i % half) * 2, (i / half) * 2);
+                let mut sum = [0.0; 3];
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let texel = faces[face][(y + dy).min(size - 1) * size + (x + dx).min(size - 1)];
+                    for c in 0..3 {
+                        sum[c] += texel[c] * 0.25;
+                    }
+                }
+                sum
+            })
+            .collect()
+    })
+}
+
+// integrates the cosine weighted light over the hemisphere around every texel's direction
+fn irradiance(faces: &Faces, size: usize) -> Faces {
+    // direction, color and solid angle of every source texel
+    let samples: Vec<([f32; 3], [f32; 3], f32)> = (0..6)
+        .flat_map(|face| {
+            face_directions(face, size)
+                .zip(&faces[face])
+                .enumerate()
+                .map(move |(i, (direction, color))| {
+                    let s = 2.0 * ((i % size) as f32 + 0.5) / size as f32 - 1.0;
+                    let t = 2.0 * ((i / size) as f32 + 0.5) / size as f32 - 1.0;
+                    let solid_angle = 4.0 / (size * size) as f32 / (s * s + t * t + 1.0).powf(1.5);
+                    (direction, *color, solid_angle)
+                })
+        })
+        .collect();
+
+    std::array::from_fn(|face| {
+        face_directions(face, IRRADIANCE_SIZE)
+            .map(|normal| {
+                let mut sum = [0.0; 3];
+                for (direction, color, solid_angle) in &samples {
+                    let weight = dot(normal, *direction).max(0.0) * solid_angle / PI;
+                    for c in 0..3 {
+                        sum[c] += color[c] * weight;
+                    }
+                }
+                sum
+            })
+            .collect()
+    })
+}
+
+// `levels` are the mip levels, each half the size of the last
+fn upload(
+    facade: &dyn Facade,
+    size: usize,
+    levels: &[Faces],
+) -> Result<Cubemap, Box<dyn std::error::Error>> {
+    let cubemap = Cubemap::empty_with_format(
+        facade,
+        glium::texture::UncompressedFloatFormat::F16F16F16F16,
+        glium::texture::MipmapsOption::EmptyMipmapsMax(levels.len() as u32 - 1),
+        size as u32,
+    )?;
+
+    for (level, faces) in levels.iter().enumerate() {
+        let level_size = (size >> level).max(1) as u32;
+        let mipmap = cubemap
+            .mipmap(level as u32)
+            .ok_or("Cubemap mip level out of range")?;
+
+        // faces are drawn into by copying from a 2D texture, glium can't upload to them directly
+        for (layer, face) in LAYERS.into_iter().zip(faces) {
+            let image = glium::texture::RawImage2d {
+                data: Cow::Owned(face.iter().flat_map(|c| [c[0], c[1], c[2], 1.0]).collect()),
+                width: level_size,
+                height: level_size,
+                format: glium::texture::ClientFormat::F32F32F32F32,
+            };
+            let texture = glium::texture::Texture2d::with_format(
+                facade,
+                image,
+                glium::texture::UncompressedFloatFormat::F16F16F16F16,
+                glium::texture::MipmapsOption::NoMipmap,
+            )?;
+
+            let framebuffer =
+                glium::framebuffer::SimpleFrameBuffer::new(facade, mipmap.image(layer))?;
+            texture.as_surface().blit_whole_color_to(
+                &framebuffer,
+                &glium::BlitTarget {
+                    left: 0,
+                    bottom: 0,
+                    width: level_size as i32,
+                    height: level_size as i32,
+                },
+                glium::uniforms::MagnifySamplerFilter::Nearest,
+            );
+        }
+    }
+
+    Ok(cubemap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: [f32; 3], expected: [f32; 3], tolerance: f32) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(
+                (a - e).abs() <= tolerance,
+                "{:?} isn't close to {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn faces_point_along_their_axes() {
+        let axes = [
+            [1.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.0, 0.0, -1.0],
+        ];
+        for (face, axis) in axes.into_iter().enumerate() {
+            let centre: Vec<_> = face_directions(face, 1).collect();
+            assert_eq!(centre.len(), 1);
+            assert_close(centre[0], axis, 1e-6);
+        }
+    }
+
+    #[test]
+    fn face_rows_run_top_to_bottom() {
+        // texel centres of a 2x2 face are half way to its edges
+        let (edge, axis) = (1.0 / 6.0_f32.sqrt(), 2.0 / 6.0_f32.sqrt());
+
+        // the first texel of +z is up and towards -x, the last is down and towards +x
+        let texels: Vec<_> = face_directions(4, 2).collect();
+        assert_close(texels[0], [-edge, edge, axis], 1e-6);
+        assert_close(texels[3], [edge, -edge, axis], 1e-6);
+
+        // +y is seen from below with -z at the top
+        let texels: Vec<_> = face_directions(2, 2).collect();
+        assert_close(texels[0], [-edge, axis, -edge], 1e-6);
+    }
+
+    #[test]
+    fn constant_color_stays_constant_down_the_mip_chain() {
+        let color = [0.25, 0.5, 1.0];
+        let levels = mip_chain(std::array::from_fn(|_| vec![color; 8 * 8]), 8);
+
+        assert_eq!(levels.len(), 4);
+        for (level, faces) in levels.iter().enumerate() {
+            for face in faces {
+                assert_eq!(face.len(), (8 >> level) * (8 >> level));
+                for texel in face {
+                    assert_close(*texel, color, 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn constant_color_irradiance_is_the_color() {
+        // the cosine weighted hemisphere integrates to pi, which irradiance is divided by
+        let color = [0.25, 0.5, 1.0];
+        let faces = irradiance(&std::array::from_fn(|_| vec![color; 8 * 8]), 8);
+
+        for face in &faces {
+            assert_eq!(face.len(), IRRADIANCE_SIZE * IRRADIANCE_SIZE);
+            for texel in face {
+                assert_close(*texel, color, 0.02);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_images_are_rejected() {
+        let image = image::DynamicImage::new_rgb8(0, 0);
+        assert!(linear_pixels(image).is_err());
+    }
+}
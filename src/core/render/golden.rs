@@ -0,0 +1,218 @@
+use crate::*;
+use std::path::{Path, PathBuf};
+
+// reference images live here, `<name>.actual.png` and `<name>.diff.png` are written next to them
+// when a comparison fails
+const GOLDEN_DIR: &str = "tests/golden";
+
+// set to overwrite the reference images with whatever is rendered now
+const UPDATE_GOLDEN_VAR: &str = "UPDATE_GOLDEN";
+
+// golden runs step the clock by a fixed amount per frame so animations render the same every time
+const GOLDEN_FRAME_TIME: f64 = 1.0 / 60.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct GoldenTolerance {
+    // how far apart a single channel can be before the pixel counts as different
+    pub channel: u8,
+    // fraction of pixels that may differ, different drivers rasterize edges a little differently
+    pub max_mismatched: f64,
+}
+
+impl Default for GoldenTolerance {
+    fn default() -> Self {
+        GoldenTolerance {
+            channel: 8,
+            max_mismatched: 0.001,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ImageDiff {
+    pub mismatched_pixels: usize,
+    pub total_pixels: usize,
+    pub max_difference: u8,
+    // differing pixels in red over a dimmed copy of the expected image
+    pub diff_image: image::RgbaImage,
+}
+
+impl ImageDiff {
+    pub fn mismatched_fraction(&self) -> f64 {
+        self.mismatched_pixels as f64 / self.total_pixels.max(1) as f64
+    }
+}
+
+pub fn compare_images(
+    actual: &image::RgbaImage,
+    expected: &image::RgbaImage,
+    tolerance: u8,
+) -> Result<ImageDiff, Box<dyn std::error::Error>> {
+    if actual.dimensions() != expected.dimensions() {
+        return Err(format!(
+            "Image is {:?} but expected {:?}",
+            actual.dimensions(),
+            expected.dimensions()
+        )
+        .into());
+    }
+
+    let mut diff_image = image::RgbaImage::new(actual.width(), actual.height());
+    let mut mismatched_pixels = 0;
+    let mut max_difference = 0;
+
+    for ((actual, expected), diff) in actual
+        .pixels()
+        .zip(expected.pixels())
+        .zip(diff_image.pixels_mut())
+    {
+        let difference = actual
+            .0
+            .iter()
+            .zip(expected.0.iter())
+            .map(|(a, e)| a.abs_diff(*e))
+            .max()
+            .unwrap_or(0);
+        max_difference = max_difference.max(difference);
+
+        *diff = if difference > tolerance {
+            mismatched_pixels += 1;
+            image::Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, _] = expected.0;
+            image::Rgba([r / 4, g / 4, b / 4, 255])
+        };
+    }
+
+    Ok(ImageDiff {
+        mismatched_pixels,
+        total_pixels: (actual.width() * actual.height()) as usize,
+        max_difference,
+        diff_image,
+    })
+}
+
+fn golden_path(name: &str, suffix: &str) -> PathBuf {
+    Path::new(GOLDEN_DIR).join(format!("{}{}.png", name, suffix))
+}
+
+// compares against `tests/golden/<name>.png`, recording it instead when `UPDATE_GOLDEN` is set
+pub fn check_golden(
+    name: &str,
+    actual: &image::RgbaImage,
+    tolerance: GoldenTolerance,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let expected_path = golden_path(name, "");
+
+    if std::env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+        std::fs::create_dir_all(GOLDEN_DIR)?;
+        actual.save(&expected_path)?;
+        println!("Wrote golden image {}", expected_path.display());
+        return Ok(());
+    }
+
+    if !expected_path.exists() {
+        return Err(format!(
+            "No golden image at {}, run with {}=1 to record it",
+            expected_path.display(),
+            UPDATE_GOLDEN_VAR
+        )
+        .into());
+    }
+
+    let expected = image::open(&expected_path)?.to_rgba8();
+    let diff = compare_images(actual, &expected, tolerance.channel)?;
+
+    if diff.mismatched_fraction() <= tolerance.max_mismatched {
+        println!("{} matches {}", name, expected_path.display());
+        return Ok(());
+    }
+
+    actual.save(golden_path(name, ".actual"))?;
+    diff.diff_image.save(golden_path(name, ".diff"))?;
+
+    Err(format!(
+        "{} differs from {}: {} of {} pixels are off by more than {} (at most {})",
+        name,
+        expected_path.display(),
+        diff.mismatched_pixels,
+        diff.total_pixels,
+        tolerance.channel,
+        diff.max_difference
+    )
+    .into())
+}
+
+// renders `frames` frames headless, checks the last one against its golden image and closes
+pub struct GoldenImagePlugin {
+    pub name: String,
+    pub frames: u32,
+    pub tolerance: GoldenTolerance,
+}
+
+impl GoldenImagePlugin {
+    pub fn new(name: &str) -> Self {
+        GoldenImagePlugin {
+            name: name.to_string(),
+            frames: 10,
+            tolerance: GoldenTolerance::default(),
+        }
+    }
+}
+
+impl Plugin for GoldenImagePlugin {
+    fn build(&self, app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+        if !app.conf.headless {
+            return Err("GoldenImagePlugin only works in headless apps".into());
+        }
+
+        app.game_state.remove_resource::<Time>();
+
+        app.add_resource(Time::new_manual())
+            .add_resource(GoldenImageCapture {
+                name: self.name.clone(),
+                frames_left: self.frames,
+                tolerance: self.tolerance,
+            })
+            .add_system(
+                get_capture_golden_system().with_error_policy(ErrorPolicy::Close),
+                SystemType::Update,
+            );
+
+        Ok(())
+    }
+}
+
+// component type 12
+#[derive(Debug)]
+pub struct GoldenImageCapture {
+    pub name: String,
+    pub frames_left: u32,
+    pub tolerance: GoldenTolerance,
+}
+impl_resource!(GoldenImageCapture, 12, debug);
+
+//uses GameState so it runs after the frame has been drawn
+create_system!(capture_golden, get_capture_golden_system;
+    uses GameState, RenderResource, GoldenImageCapture, Time);
+async fn capture_golden(game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+    game_state
+        .require_resource_mut::<Time>()?
+        .advance(GOLDEN_FRAME_TIME);
+
+    let capture = game_state.require_resource_mut::<GoldenImageCapture>()?;
+    capture.frames_left = capture.frames_left.saturating_sub(1);
+    if capture.frames_left > 0 {
+        return Ok(());
+    }
+
+    let capture = game_state.require_resource::<GoldenImageCapture>()?;
+    let pixels = game_state
+        .require_resource::<RenderResource>()?
+        .read_pixels()?;
+
+    check_golden(&capture.name, &pixels, capture.tolerance)?;
+
+    game_state.close();
+    Ok(())
+}
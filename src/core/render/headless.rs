@@ -0,0 +1,101 @@
+use glium::backend::{Backend, Context, Facade};
+use glium::debug::DebugCallbackBehavior;
+use glium::glutin::api::egl::context::PossiblyCurrentContext;
+use glium::glutin::api::egl::device::Device;
+use glium::glutin::api::egl::display::Display;
+use glium::glutin::config::{Api, ConfigSurfaceTypes, ConfigTemplateBuilder};
+use glium::glutin::context::{ContextApi, ContextAttributesBuilder, Version};
+use glium::glutin::prelude::*;
+use glium::SwapBuffersError;
+use std::cell::Cell;
+use std::ffi::{c_void, CString};
+use std::rc::Rc;
+
+// an OpenGL context without any surface, everything is drawn into framebuffer objects
+struct HeadlessBackend {
+    display: Display,
+    context: PossiblyCurrentContext,
+    dimensions: Cell<(u32, u32)>,
+}
+
+unsafe impl Backend for HeadlessBackend {
+    fn swap_buffers(&self) -> Result<(), SwapBuffersError> {
+        Ok(())
+    }
+
+    unsafe fn get_proc_address(&self, symbol: &str) -> *const c_void {
+        let symbol = CString::new(symbol).unwrap();
+        self.display.get_proc_address(&symbol)
+    }
+
+    fn get_framebuffer_dimensions(&self) -> (u32, u32) {
+        self.dimensions.get()
+    }
+
+    fn resize(&self, new_size: (u32, u32)) {
+        self.dimensions.set(new_size);
+    }
+
+    fn is_current(&self) -> bool {
+        self.context.is_current()
+    }
+
+    unsafe fn make_current(&self) {
+        self.context.make_current_surfaceless().unwrap();
+    }
+}
+
+pub struct HeadlessDisplay {
+    context: Rc<Context>,
+}
+
+impl std::fmt::Debug for HeadlessDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "HeadlessDisplay {{ dimensions: {:?} }}",
+            self.context.get_framebuffer_dimensions()
+        )
+    }
+}
+
+impl Facade for HeadlessDisplay {
+    fn get_context(&self) -> &Rc<Context> {
+        &self.context
+    }
+}
+
+impl HeadlessDisplay {
+    // uses the first EGL device, on machines without a gpu this is mesa's software rasterizer
+    pub fn new(dimensions: (u32, u32)) -> Result<Self, Box<dyn std::error::Error>> {
+        let device = Device::query_devices()?
+            .next()
+            .ok_or("No EGL device available")?;
+
+        let display = unsafe { Display::with_device(&device, None)? };
+
+        let template = ConfigTemplateBuilder::new()
+            .with_surface_type(ConfigSurfaceTypes::empty())
+            .with_api(Api::OPENGL)
+            .build();
+        let config = unsafe { display.find_configs(template)? }
+            .next()
+            .ok_or("No EGL config supports surfaceless OpenGL")?;
+
+        let attributes = ContextAttributesBuilder::new()
+            .with_context_api(ContextApi::OpenGl(Some(Version::new(3, 3))))
+            .build(None);
+        let context =
+            unsafe { display.create_context(&config, &attributes)? }.make_current_surfaceless()?;
+
+        let backend = HeadlessBackend {
+            display,
+            context,
+            dimensions: Cell::new(dimensions),
+        };
+
+        let context = unsafe { Context::new(backend, true, DebugCallbackBehavior::Ignore)? };
+
+        Ok(Self { context })
+    }
+}
@@ -0,0 +1,219 @@
+use crate::*;
+use std::any::Any;
+
+// the shaders get these as `MAX_*_LIGHTS` defines, extra lights furthest from the camera are
+// dropped
+pub const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+pub const MAX_POINT_LIGHTS: usize = 16;
+pub const MAX_SPOT_LIGHTS: usize = 8;
+
+// component type 14
+#[derive(Debug, Clone)]
+pub struct DirectionalLight {
+    // the direction the light travels in
+    pub direction: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub shadow: Option<ShadowSettings>,
+}
+impl_component!(DirectionalLight, 14);
+
+// component type 15
+// positioned by the entity's `Transform`
+#[derive(Debug, Clone)]
+pub struct PointLight {
+    pub color: [f32; 3],
+    pub intensity: f32,
+    // no light reaches past this distance
+    pub range: f32,
+}
+impl_component!(PointLight, 15);
+
+// component type 16
+// positioned by the entity's `Transform`
+#[derive(Debug, Clone)]
+pub struct SpotLight {
+    pub direction: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub range: f32,
+    // full brightness inside the inner angle, fading to nothing at the outer angle, in degrees
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    pub shadow: Option<ShadowSettings>,
+}
+impl_component!(SpotLight, 16);
+
+// component type 17
+// light that reaches every surface from every direction
+#[derive(Debug, Clone)]
+pub struct AmbientLight {
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+impl_resource!(AmbientLight, 17, debug);
+
+fn scaled(color: [f32; 3], intensity: f32) -> [f32; 3] {
+    [
+        color[0] * intensity,
+        color[1] * intensity,
+        color[2] * intensity,
+    ]
+}
+
+fn distance_squared(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+// lights with the entity's position, closest to `camera_position` first and capped at `max`
+fn positioned_lights<T: Component>(
+    game_state: &GameState,
+    component_type: ComponentType,
+    camera_position: [f32; 3],
+    max: usize,
+) -> Vec<(&T, [f32; 3])> {
+    let mut lights: Vec<(&T, [f32; 3])> = game_state
+        .get_entities_with::<T>(component_type)
+        .into_iter()
+        .filter_map(|entity| {
+            let light = entity.get_component::<T>(component_type)?;
+            let transform = entity.get_component::<Transform>(Transform::get_component_type())?;
+            Some((light, transform.position))
+        })
+        .collect();
+
+    lights.sort_by(|(_, a), (_, b)| {
+        distance_squared(*a, camera_position).total_cmp(&distance_squared(*b, camera_position))
+    });
+    lights.truncate(max);
+    lights
+}
+
+#[derive(Debug)]
+pub struct SceneLights {
+    // uniform values for every light in the scene
+    pub values: Vec<(String, MaterialValue)>,
+    // lights that need a shadow map, in the order of their `shadow_index`
+    pub shadow_casters: Vec<ShadowCaster>,
+}
+
+// the light's shadow map or -1, stops handing them out after `max_shadows`
+fn shadow_index(
+    casters: &mut Vec<ShadowCaster>,
+    caster: Option<ShadowCaster>,
+    max_shadows: usize,
+) -> i32 {
+    match caster {
+        Some(caster) if casters.len() < max_shadows => {
+            casters.push(caster);
+            casters.len() as i32 - 1
+        }
+        _ => -1,
+    }
+}
+
+// gathered once per frame
+pub fn gather_lights(
+    game_state: &GameState,
+    camera_position: [f32; 3],
+    max_shadows: usize,
+) -> SceneLights {
+    let mut values = Vec::new();
+    let mut shadow_casters = Vec::new();
+
+    let ambient = game_state
+        .get_resource::<AmbientLight>()
+        .map_or([0.0; 3], |ambient| scaled(ambient.color, ambient.intensity));
+    values.push(("ambient_light".to_string(), MaterialValue::Vec3(ambient)));
+
+    let directional_lights =
+        game_state.get_components::<DirectionalLight>(DirectionalLight::get_component_type());
+    let directional_lights =
+        &directional_lights[..directional_lights.len().min(MAX_DIRECTIONAL_LIGHTS)];
+    for (i, light) in directional_lights.iter().enumerate() {
+        let name = |field: &str| format!("directional_lights[{}].{}", i, field);
+        values.push((name("direction"), normalize(light.direction).into()));
+        values.push((name("color"), scaled(light.color, light.intensity).into()));
+
+        let caster = light
+            .shadow
+            .as_ref()
+            .map(|settings| ShadowCaster::directional(light.direction, camera_position, settings));
+        values.push((
+            name("shadow_index"),
+            shadow_index(&mut shadow_casters, caster, max_shadows).into(),
+        ));
+    }
+    values.push((
+        "directional_light_count".to_string(),
+        MaterialValue::Int(directional_lights.len() as i32),
+    ));
+
+    let point_lights = positioned_lights::<PointLight>(
+        game_state,
+        PointLight::get_component_type(),
+        camera_position,
+        MAX_POINT_LIGHTS,
+    );
+    for (i, (light, position)) in point_lights.iter().enumerate() {
+        let name = |field: &str| format!("point_lights[{}].{}", i, field);
+        values.push((name("position"), (*position).into()));
+        values.push((name("color"), scaled(light.color, light.intensity).into()));
+        values.push((name("range"), light.range.into()));
+    }
+    values.push((
+        "point_light_count".to_string(),
+        MaterialValue::Int(point_lights.len() as i32),
+    ));
+
+    let spot_lights = positioned_lights::<SpotLight>(
+        game_state,
+        SpotLight::get_component_type(),
+        camera_position,
+        MAX_SPOT_LIGHTS,
+    );
+    for (i, (light, position)) in spot_lights.iter().enumerate() {
+        let name = |field: &str| format!("spot_lights[{}].{}", i, field);
+        values.push((name("position"), (*position).into()));
+        values.push((name("direction"), normalize(light.direction).into()));
+        values.push((name("color"), scaled(light.color, light.intensity).into()));
+        values.push((name("range"), light.range.into()));
+        values.push((
+            name("inner_cos"),
+            light.inner_angle.to_radians().cos().into(),
+        ));
+        values.push((
+            name("outer_cos"),
+            light.outer_angle.to_radians().cos().into(),
+        ));
+
+        let caster = light.shadow.as_ref().map(|settings| {
+            ShadowCaster::spot(
+                *position,
+                light.direction,
+                light.outer_angle,
+                light.range,
+                settings,
+            )
+        });
+        values.push((
+            name("shadow_index"),
+            shadow_index(&mut shadow_casters, caster, max_shadows).into(),
+        ));
+    }
+    values.push((
+        "spot_light_count".to_string(),
+        MaterialValue::Int(spot_lights.len() as i32),
+    ));
+
+    for (i, caster) in shadow_casters.iter().enumerate() {
+        let name = |field: &str| format!("shadows[{}].{}", i, field);
+        values.push((name("matrix"), caster.matrix.0.into()));
+        values.push((name("bias"), caster.bias.into()));
+    }
+
+    SceneLights {
+        values,
+        shadow_casters,
+    }
+}
@@ -0,0 +1,301 @@
+use crate::*;
+use glium::backend::Facade;
+use glium::uniforms::{UniformValue, Uniforms};
+use std::any::Any;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaterialValue {
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    Mat4([[f32; 4]; 4]),
+    Int(i32),
+    Bool(bool),
+}
+
+impl MaterialValue {
+    fn as_uniform(&self) -> UniformValue<'_> {
+        match *self {
+            MaterialValue::Float(value) => UniformValue::Float(value),
+            MaterialValue::Vec2(value) => UniformValue::Vec2(value),
+            MaterialValue::Vec3(value) => UniformValue::Vec3(value),
+            MaterialValue::Vec4(value) => UniformValue::Vec4(value),
+            MaterialValue::Mat4(value) => UniformValue::Mat4(value),
+            MaterialValue::Int(value) => UniformValue::SignedInt(value),
+            MaterialValue::Bool(value) => UniformValue::Bool(value),
+        }
+    }
+}
+
+macro_rules! impl_material_value {
+    ($type:ty, $variant:ident) => {
+        impl From<$type> for MaterialValue {
+            fn from(value: $type) -> Self {
+                MaterialValue::$variant(value)
+            }
+        }
+    };
+}
+
+impl_material_value!(f32, Float);
+impl_material_value!([f32; 2], Vec2);
+impl_material_value!([f32; 3], Vec3);
+impl_material_value!([f32; 4], Vec4);
+impl_material_value!([[f32; 4]; 4], Mat4);
+impl_material_value!(i32, Int);
+impl_material_value!(bool, Bool);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BlendMode {
+    #[default]
+    Opaque,
+    // opaque, but fragments with an alpha below the cutoff are discarded
+    Cutout(f32),
+    // the rest are drawn after opaque objects, back to front, without writing depth
+    Alpha,
+    Additive,
+    // for colors that were already multiplied by their alpha
+    Premultiplied,
+}
+
+impl BlendMode {
+    pub fn is_transparent(&self) -> bool {
+        matches!(
+            self,
+            BlendMode::Alpha | BlendMode::Additive | BlendMode::Premultiplied
+        )
+    }
+
+    // `alpha_cutoff` in the shaders
+    pub fn alpha_cutoff(&self) -> f32 {
+        match self {
+            BlendMode::Cutout(cutoff) => *cutoff,
+            _ => 0.0,
+        }
+    }
+
+    // `opaque` with this mode's blending
+    pub fn draw_parameters<'a>(
+        &self,
+        opaque: &glium::DrawParameters<'a>,
+    ) -> glium::DrawParameters<'a> {
+        use glium::{BlendingFunction, LinearBlendingFactor};
+
+        let color = match self {
+            BlendMode::Opaque | BlendMode::Cutout(_) => return opaque.clone(),
+            BlendMode::Alpha => BlendingFunction::Addition {
+                source: LinearBlendingFactor::SourceAlpha,
+                destination: LinearBlendingFactor::OneMinusSourceAlpha,
+            },
+            BlendMode::Additive => BlendingFunction::Addition {
+                source: LinearBlendingFactor::SourceAlpha,
+                destination: LinearBlendingFactor::One,
+            },
+            BlendMode::Premultiplied => BlendingFunction::Addition {
+                source: LinearBlendingFactor::One,
+                destination: LinearBlendingFactor::OneMinusSourceAlpha,
+            },
+        };
+
+        glium::DrawParameters {
+            depth: glium::Depth {
+                write: false,
+                ..opaque.depth
+            },
+            blend: glium::Blend {
+                color,
+                alpha: BlendingFunction::Addition {
+                    source: LinearBlendingFactor::One,
+                    destination: LinearBlendingFactor::OneMinusSourceAlpha,
+                },
+                constant_value: (0.0, 0.0, 0.0, 0.0),
+            },
+            ..opaque.clone()
+        }
+    }
+}
+
+// the metallic-roughness shader `Material::default` uses
+pub const DEFAULT_SHADER: &str = "default";
+
+// component type 13
+#[derive(Debug, Clone)]
+pub struct Material {
+    // name of the shader in `SHADER_DIR`
+    pub shader: String,
+    pub values: Vec<(String, MaterialValue)>,
+    // shared so many objects can use the same texture
+    pub textures: Vec<(String, Rc<Texture>)>,
+    pub blend_mode: BlendMode,
+}
+impl_component!(Material, 13);
+
+// equal materials bind the same uniforms, so their objects can share a draw call
+impl PartialEq for Material {
+    fn eq(&self, other: &Self) -> bool {
+        self.shader == other.shader
+            && self.blend_mode == other.blend_mode
+            && self.values == other.values
+            && self.textures.len() == other.textures.len()
+            && self.textures.iter().zip(&other.textures).all(
+                |((name, texture), (other_name, other_texture))| {
+                    name == other_name && Rc::ptr_eq(texture, other_texture)
+                },
+            )
+    }
+}
+
+// a plain metallic-roughness material, maps set with `with_texture` are multiplied by these
+// factors
+impl Default for Material {
+    fn default() -> Self {
+        Material::new(DEFAULT_SHADER)
+            .with_value("base_color_factor", [1.0, 1.0, 1.0, 1.0])
+            .with_value("metallic_factor", 0.0)
+            .with_value("roughness_factor", 0.5)
+            .with_value("emissive_factor", [0.0, 0.0, 0.0])
+            .with_value("occlusion_strength", 1.0)
+            .with_value("normal_scale", 1.0)
+    }
+}
+
+impl Material {
+    pub fn new(shader: &str) -> Material {
+        Material {
+            shader: shader.to_string(),
+            values: Vec::new(),
+            textures: Vec::new(),
+            blend_mode: BlendMode::Opaque,
+        }
+    }
+
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    pub fn with_value(mut self, name: &str, value: impl Into<MaterialValue>) -> Self {
+        self.set_value(name, value);
+        self
+    }
+
+    pub fn with_texture(mut self, name: &str, texture: Rc<Texture>) -> Self {
+        self.set_texture(name, texture);
+        self
+    }
+
+    pub fn set_value(&mut self, name: &str, value: impl Into<MaterialValue>) {
+        let value = value.into();
+        match self.values.iter_mut().find(|(n, _)| n == name) {
+            Some((_, old)) => *old = value,
+            None => self.values.push((name.to_string(), value)),
+        }
+    }
+
+    pub fn set_texture(&mut self, name: &str, texture: Rc<Texture>) {
+        match self.textures.iter_mut().find(|(n, _)| n == name) {
+            Some((_, old)) => *old = texture,
+            None => self.textures.push((name.to_string(), texture)),
+        }
+    }
+
+    pub fn get_value(&self, name: &str) -> Option<MaterialValue> {
+        self.values
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+// a material's values and textures plus the values the renderer sets per draw and per frame,
+// material values with the same name as a renderer value are ignored
+pub struct MaterialUniforms<'a> {
+    pub material: &'a Material,
+    pub globals: &'a [(&'static str, MaterialValue)],
+    pub scene: &'a [(String, MaterialValue)],
+    pub shadow_maps: Option<&'a ShadowMaps>,
+    pub environment: Option<&'a Environment>,
+    pub default_textures: &'a [(&'static str, Texture)],
+}
+
+impl Uniforms for MaterialUniforms<'_> {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F) {
+        for (name, value) in self.globals {
+            visit(name, value.as_uniform());
+        }
+
+        for (name, value) in self.scene {
+            visit(name, value.as_uniform());
+        }
+
+        for (name, value) in &self.material.values {
+            let is_global = self.globals.iter().any(|(global, _)| global == name)
+                || self.scene.iter().any(|(global, _)| global == name);
+            if !is_global {
+                visit(name, value.as_uniform());
+            }
+        }
+
+        for (name, texture) in &self.material.textures {
+            visit(name, texture.as_uniform());
+        }
+
+        for (name, texture) in self.default_textures {
+            if !self.material.textures.iter().any(|(n, _)| n == name) {
+                visit(name, texture.as_uniform());
+            }
+        }
+
+        if let Some(shadow_maps) = self.shadow_maps {
+            visit(
+                "shadow_maps",
+                UniformValue::DepthTexture2dArray(&shadow_maps.depth, Some(shadow_maps.sampler())),
+            );
+        }
+
+        if let Some(environment) = self.environment {
+            let sampler = Some(environment.sampler());
+            visit(
+                "environment_map",
+                UniformValue::Cubemap(&environment.skybox, sampler),
+            );
+            visit(
+                "irradiance_map",
+                UniformValue::Cubemap(&environment.irradiance, sampler),
+            );
+            visit(
+                "environment_intensity",
+                UniformValue::Float(environment.intensity),
+            );
+            visit(
+                "environment_max_lod",
+                UniformValue::Float(environment.max_lod()),
+            );
+        }
+    }
+}
+
+// chosen so that a missing map leaves its factor unchanged
+pub fn default_textures(
+    facade: &dyn Facade,
+) -> Result<Vec<(&'static str, Texture)>, Box<dyn std::error::Error>> {
+    const WHITE: [u8; 4] = [255, 255, 255, 255];
+    // +z in tangent space, straight out of the surface
+    const FLAT_NORMAL: [u8; 4] = [128, 128, 255, 255];
+
+    Ok(vec![
+        ("base_color_map", Texture::from_color(facade, WHITE, true)?),
+        (
+            "metallic_roughness_map",
+            Texture::from_color(facade, WHITE, false)?,
+        ),
+        (
+            "normal_map",
+            Texture::from_color(facade, FLAT_NORMAL, false)?,
+        ),
+        ("occlusion_map", Texture::from_color(facade, WHITE, false)?),
+        ("emissive_map", Texture::from_color(facade, WHITE, true)?),
+    ])
+}
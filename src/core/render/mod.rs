@@ -1,54 +1,83 @@
 use crate::*;
+use glium::backend::Facade;
 use glium::*;
 use glutin::surface::WindowSurface;
 use std::any::Any;
 
-const VERTEX_SHADER_SRC: &str = r#"
-    #version 140
-    uniform mat4 model;
-    uniform mat4 view;
-    uniform mat4 proj;
+pub mod batch;
+pub use batch::*;
 
-    in vec3 position;
-    in vec3 normal;
-    in vec2 uv;
+pub mod capture;
+pub use capture::*;
 
-    out vec3 vNormal;
-    out vec2 vUv;
+pub mod debug;
+pub use debug::*;
 
-    void main() {
-        vNormal = (model * vec4(normal, 0.0)).xyz;
-        vUv = uv;
+pub mod environment;
+pub use environment::*;
 
-        gl_Position = proj * view * model * vec4(position, 1.0);
-    }
-"#;
+pub mod golden;
+pub use golden::*;
 
-const FRAGMENT_SHADER_SRC: &str = r#"
-    #version 140
-    uniform sampler2D texture;
-    uniform vec3 light;
-    uniform float ambient;
+pub mod headless;
+pub use headless::*;
 
-    in vec3 vNormal;
-    in vec2 vUv;
+pub mod light;
+pub use light::*;
 
-    out vec4 color;
+pub mod material;
+pub use material::*;
 
-    void main() {
-        float intensity = clamp(dot(vNormal, light), 0.0, 1.0) * (1.0 - ambient) + ambient;
+pub mod post;
+pub use post::*;
 
-        color = texture2D(texture, vUv) * vec4(intensity, intensity, intensity, 1.0);
-    }
-"#;
+pub mod shader;
+pub use shader::*;
+
+pub mod shadow;
+pub use shadow::*;
+
+pub mod text;
+pub use text::*;
+
+pub mod viewport;
+pub use viewport::*;
+
+#[derive(Debug)]
+pub enum RenderTarget {
+    Window {
+        window: winit::window::Window,
+        display: glium::Display<WindowSurface>,
+    },
+    // draws into textures instead of a window so it works on machines without a display
+    Headless {
+        display: HeadlessDisplay,
+        color: glium::texture::SrgbTexture2d,
+        depth: glium::texture::DepthTexture2d,
+    },
+}
 
 // component type 0
 #[derive(Debug)]
 pub struct RenderResource<'a> {
-    pub window: winit::window::Window,
-    pub display: glium::Display<WindowSurface>,
-    pub program: glium::Program,
+    pub target: RenderTarget,
+    pub shaders: ShaderCache,
+    // created on the first frame unless shadows are turned off
+    pub shadow_maps: Option<ShadowMaps>,
+    // one for every viewport size used by a camera with `PostProcessing`
+    pub post_targets: Vec<PostTargets>,
+    // bound for maps a material doesn't set
+    pub default_textures: Vec<(&'static str, Texture)>,
+    // black, lights the scene when there's no `Environment` resource
+    pub default_environment: Environment,
+    // `DEFAULT_FONT`, for text that doesn't set a font
+    pub default_font: Option<Font>,
     pub params: glium::DrawParameters<'a>,
+    pub stats: RenderStats,
+
+    // handled after the current frame is finished
+    pub captures: Vec<CaptureRequest>,
+    pub sequence: Option<ImageSequence>,
 }
 impl_resource!(RenderResource<'static>, 0);
 
@@ -57,12 +86,132 @@ impl_resource!(RenderResource<'static>, 0);
 pub struct RenderObject;
 impl_component!(RenderObject, 1);
 
+#[derive(Debug)]
+pub enum TextureData {
+    // color data, decoded to linear when sampled
+    Srgb(glium::texture::SrgbTexture2d),
+    Linear(glium::texture::Texture2d),
+}
+
 // component type 4
 #[derive(Debug)]
-pub struct Texture<'a> {
-    pub sampler: glium::uniforms::Sampler<'a, glium::texture::Texture2d>,
+pub struct Texture {
+    pub texture: TextureData,
+    pub sampler: glium::uniforms::SamplerBehavior,
+}
+impl_component!(Texture, 4);
+
+impl Texture {
+    // for colors, like base color and emissive maps
+    pub fn load<F: Facade + ?Sized>(
+        path: &str,
+        display: &F,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load_with(path, display, true)
+    }
+
+    // for data, like normal, metallic-roughness and occlusion maps
+    pub fn load_linear<F: Facade + ?Sized>(
+        path: &str,
+        display: &F,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load_with(path, display, false)
+    }
+
+    // a single pixel texture
+    pub fn from_color<F: Facade + ?Sized>(
+        display: &F,
+        color: [u8; 4],
+        srgb: bool,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let image = glium::texture::RawImage2d::from_raw_rgba(color.to_vec(), (1, 1));
+        Self::from_image(display, image, srgb)
+    }
+
+    fn load_with<F: Facade + ?Sized>(
+        path: &str,
+        display: &F,
+        srgb: bool,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let image_file = std::fs::File::open(path)?;
+        let image_reader = std::io::BufReader::new(image_file);
+
+        let format = image::ImageFormat::from_path(path)?;
+        let image = image::load(image_reader, format)?.to_rgba8();
+
+        let image_dimensions = image.dimensions();
+
+        let image =
+            glium::texture::RawImage2d::from_raw_rgba_reversed(&image.into_raw(), image_dimensions);
+
+        Self::from_image(display, image, srgb)
+    }
+
+    fn from_image<F: Facade + ?Sized>(
+        display: &F,
+        image: glium::texture::RawImage2d<u8>,
+        srgb: bool,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let texture = if srgb {
+            TextureData::Srgb(glium::texture::SrgbTexture2d::new(display, image)?)
+        } else {
+            TextureData::Linear(glium::texture::Texture2d::new(display, image)?)
+        };
+
+        let sampler = glium::uniforms::SamplerBehavior {
+            magnify_filter: glium::uniforms::MagnifySamplerFilter::Linear,
+            minify_filter: glium::uniforms::MinifySamplerFilter::LinearMipmapLinear,
+            ..Default::default()
+        };
+
+        Ok(Self { texture, sampler })
+    }
+
+    pub fn as_uniform(&self) -> glium::uniforms::UniformValue<'_> {
+        match &self.texture {
+            TextureData::Srgb(texture) => {
+                glium::uniforms::UniformValue::SrgbTexture2d(texture, Some(self.sampler))
+            }
+            TextureData::Linear(texture) => {
+                glium::uniforms::UniformValue::Texture2d(texture, Some(self.sampler))
+            }
+        }
+    }
+}
+
+pub struct RenderPlugin;
+
+impl Plugin for RenderPlugin {
+    fn build(&self, app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+        let renderer = match &app.event_loop {
+            Some(event_loop) => {
+                RenderResource::new(event_loop, &app.conf.window_title, app.conf.window_size)?
+            }
+            None => RenderResource::new_headless(app.conf.window_size)?,
+        };
+
+        if app.conf.shader_hot_reload {
+            app.add_system(get_reload_shaders_system(), SystemType::Update);
+        }
+
+        app.add_resource(renderer)
+            .add_resource(DebugDraw::new())
+            .add_system(get_render_system(), SystemType::Update)
+            .add_window_event_handler(handle_capture_keys)
+            .add_window_event_handler(|event, game_state| {
+                if let winit::event::WindowEvent::Resized(physical_size) = event {
+                    let dimensions = (physical_size.width, physical_size.height);
+                    for camera in
+                        game_state.get_components_mut::<Camera>(Camera::get_component_type())
+                    {
+                        camera.fit_viewport(dimensions);
+                    }
+                }
+            });
+
+        Ok(())
+    }
 }
-impl_component!(Texture<'static>, 4);
 
 impl RenderResource<'_> {
     pub fn new(
@@ -75,8 +224,48 @@ impl RenderResource<'_> {
             .with_inner_size(inner_size.0, inner_size.1)
             .build(event_loop);
 
-        let program =
-            glium::Program::from_source(&display, VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC, None)?;
+        Self::with_target(RenderTarget::Window { window, display })
+    }
+
+    pub fn new_headless(dimensions: (u32, u32)) -> Result<Self, Box<dyn std::error::Error>> {
+        // the golden image test looks for this to skip on machines without OpenGL
+        let display = HeadlessDisplay::new(dimensions)
+            .map_err(|e| format!("No headless OpenGL context: {}", e))?;
+
+        let color = glium::texture::SrgbTexture2d::empty_with_format(
+            &display,
+            glium::texture::SrgbFormat::U8U8U8U8,
+            glium::texture::MipmapsOption::NoMipmap,
+            dimensions.0,
+            dimensions.1,
+        )?;
+        let depth = glium::texture::DepthTexture2d::empty_with_format(
+            &display,
+            glium::texture::DepthFormat::I24,
+            glium::texture::MipmapsOption::NoMipmap,
+            dimensions.0,
+            dimensions.1,
+        )?;
+
+        Self::with_target(RenderTarget::Headless {
+            display,
+            color,
+            depth,
+        })
+    }
+
+    fn with_target(target: RenderTarget) -> Result<Self, Box<dyn std::error::Error>> {
+        let default_textures = default_textures(Self::target_facade(&target))?;
+        let default_environment =
+            Environment::from_color(Self::target_facade(&target), [0.0; 3])?.with_intensity(0.0);
+        // text just isn't drawn without it
+        let default_font = match Font::load(DEFAULT_FONT, Self::target_facade(&target)) {
+            Ok(font) => Some(font),
+            Err(e) => {
+                eprintln!("Error loading font {}: {}", DEFAULT_FONT, e);
+                None
+            }
+        };
 
         let params = glium::DrawParameters {
             depth: glium::Depth {
@@ -88,20 +277,68 @@ impl RenderResource<'_> {
             ..Default::default()
         };
         Ok(Self {
-            window,
-            display,
-            program,
+            target,
+            shaders: ShaderCache::new(),
+            shadow_maps: None,
+            post_targets: Vec::new(),
+            default_textures,
+            default_environment,
+            default_font,
             params,
+            stats: RenderStats::default(),
+            captures: Vec::new(),
+            sequence: None,
         })
     }
+
+    pub(crate) fn target_facade(target: &RenderTarget) -> &dyn Facade {
+        match target {
+            RenderTarget::Window { display, .. } => display,
+            RenderTarget::Headless { display, .. } => display,
+        }
+    }
+
+    // used to create buffers and textures for whichever target is in use
+    pub fn facade(&self) -> &dyn Facade {
+        Self::target_facade(&self.target)
+    }
+
+    pub fn window(&self) -> Option<&winit::window::Window> {
+        match &self.target {
+            RenderTarget::Window { window, .. } => Some(window),
+            RenderTarget::Headless { .. } => None,
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        match &self.target {
+            RenderTarget::Window { window, .. } => window.inner_size().into(),
+            RenderTarget::Headless { color, .. } => color.dimensions(),
+        }
+    }
+
+    // returns the last finished frame, top row first
+    pub fn read_pixels(&self) -> Result<image::RgbaImage, Box<dyn std::error::Error>> {
+        let pixels: glium::texture::RawImage2d<u8> = match &self.target {
+            RenderTarget::Window { display, .. } => display.read_front_buffer()?,
+            RenderTarget::Headless { color, .. } => color.read(),
+        };
+
+        let image =
+            image::RgbaImage::from_raw(pixels.width, pixels.height, pixels.data.into_owned())
+                .ok_or("Frame has the wrong size")?;
+
+        // OpenGL stores the bottom row first
+        Ok(image::imageops::flip_vertical(&image))
+    }
 }
 
 const SPEED: f32 = 5.0;
 
 create_system!(rotate_cube, get_rotate_cube_system;
     uses Transform, InputHandler);
-async fn rotate_cube(game_state: &mut GameState, t: f64, dt: f64) {
-    let input = game_state.get_resource::<InputHandler>().unwrap();
+async fn rotate_cube(game_state: &mut GameState, t: f64, dt: f64) -> SystemResult {
+    let input = game_state.require_resource::<InputHandler>()?;
     let forward = if input.is_down(winit::event::VirtualKeyCode::W) {
         dt as f32 * SPEED
     } else if input.is_down(winit::event::VirtualKeyCode::S) {
@@ -128,78 +365,556 @@ async fn rotate_cube(game_state: &mut GameState, t: f64, dt: f64) {
         pos.position[0] += right;
         pos.position[1] += forward;
     }
+
+    Ok(())
 }
 
 //uses GameState to ensure that it can unlock the scheduler lock
 create_system!(render, get_render_system;
-    uses GameState, RenderResource, RenderObject, Texture, Model, Transform, Camera);
-async fn render(game_state: &mut GameState, _t: f64, _dt: f64) {
-    let render_resource = game_state.get_resource::<RenderResource>().unwrap();
+    uses GameState, RenderResource, RenderObject, Material, Model, Transform, Camera,
+        PostProcessing, DirectionalLight, PointLight, SpotLight, AmbientLight, Environment, Time,
+        DebugDraw, Text, Ui);
+async fn render(game_state: &mut GameState, _t: f64, dt: f64) -> SystemResult {
+    let result = render_frame(game_state).await;
+
+    // debug shapes still expire when the frame couldn't be drawn
+    let real_dt = real_delta_or(game_state, dt);
+    if let Some(debug) = game_state.get_resource_mut::<DebugDraw>() {
+        debug.end_frame(real_dt);
+    }
+
+    result?;
+    process_captures(game_state)
+}
+
+async fn render_frame(game_state: &mut GameState) -> SystemResult {
+    load_shaders(game_state)?;
+    create_shadow_maps(game_state)?;
+    create_post_targets(game_state)?;
+
+    let display = match &game_state.require_resource::<RenderResource>()?.target {
+        RenderTarget::Window { display, .. } => Some(display.clone()),
+        RenderTarget::Headless { .. } => None,
+    };
 
-    // unlocks global scheduler lock to allow for FixedUpdate to run while waiting for vsync
-    unsafe {
-        (&*game_state.scheduler).force_unlock().await;
+    // unlocks global scheduler lock to allow for FixedUpdate to run while waiting for vsync. nothing
+    // borrowed from the game state is held across it, the scene is gathered once it's locked again
+    let mut window_frame = match display {
+        Some(display) => {
+            let scheduler = unsafe { &*game_state.scheduler };
+            unsafe {
+                scheduler.force_unlock().await;
+            }
+
+            let mut frame = display.draw();
+            frame.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
+
+            unsafe {
+                scheduler.force_lock().await;
+            }
+            Some(frame)
+        }
+        None => None,
+    };
+
+    let result = draw_scene(game_state, window_frame.as_mut());
+
+    // the frame has to be finished even if drawing failed, glium panics when it is dropped
+    // otherwise
+    if let Some(frame) = window_frame {
+        frame.finish()?;
     }
 
-    let mut frame = render_resource.display.draw();
-    frame.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
+    let stats = result?;
+    game_state.require_resource_mut::<RenderResource>()?.stats = stats;
+    Ok(())
+}
+
+// draws every camera's view into the window's frame, or the headless target when there is none
+fn draw_scene(
+    game_state: &GameState,
+    window_frame: Option<&mut glium::Frame>,
+) -> Result<RenderStats, Box<dyn std::error::Error>> {
+    let render_resource = game_state.require_resource::<RenderResource>()?;
+    let dimensions = render_resource.dimensions();
+
+    // without a camera, like while a scene loads, the window still gets screen text and the ui.
+    // headless runs only exist to render the scene, so there it is an error
+    let cameras = render_cameras(game_state);
+    if cameras.is_empty() && window_frame.is_none() {
+        return Err("No camera to render with".into());
+    }
 
-    unsafe {
-        (&*game_state.scheduler).force_lock().await;
+    let max_shadows = if render_resource.shadow_maps.is_some() {
+        MAX_SHADOW_MAPS
+    } else {
+        0
+    };
+    let views = cameras
+        .into_iter()
+        .map(|(entity, camera)| {
+            View::new(
+                game_state,
+                render_resource,
+                entity,
+                camera,
+                dimensions,
+                max_shadows,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut stats = RenderStats::default();
+    for view in &views {
+        let drawn: usize = view
+            .batches
+            .batches
+            .iter()
+            .map(|batch| batch.visible.len())
+            .sum();
+        let instances: usize = view
+            .batches
+            .batches
+            .iter()
+            .map(|batch| batch.instances.len())
+            .sum();
+        stats.batches += view.batches.batches.len();
+        stats.drawn += drawn;
+        stats.culled += instances - drawn;
     }
 
-    let camera: &Camera = game_state.get_components(Camera::get_component_type())[0];
+    let screen_text = gather_screen_text(game_state, render_resource.default_font.as_ref());
+    let scene = Scene {
+        environment: game_state.get_resource::<Environment>(),
+        debug: game_state.get_resource::<DebugDraw>(),
+        screen_text: &screen_text,
+        ui: game_state.get_resource::<Ui>(),
+        time: game_state
+            .get_resource::<Time>()
+            .map_or(0.0, |time| time.virtual_clock.elapsed as f32),
+    };
 
-    for entity in game_state
-        .get_entities_with::<RenderObject>(RenderObject::get_component_type())
-        .iter()
+    let frame_stats = match (window_frame, &render_resource.target) {
+        (Some(frame), _) => draw_frame(render_resource, &views, &scene, frame)?,
+        (
+            None,
+            RenderTarget::Headless {
+                display,
+                color,
+                depth,
+            },
+        ) => {
+            let mut framebuffer =
+                glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(display, color, depth)?;
+            framebuffer.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
+
+            draw_frame(render_resource, &views, &scene, &mut framebuffer)?
+        }
+        (None, RenderTarget::Window { .. }) => return Err("Window target without a frame".into()),
+    };
+    stats.draw_calls = frame_stats.draw_calls;
+    stats.shadow_draw_calls = frame_stats.shadow_draw_calls;
+    stats.post_passes = frame_stats.post_passes;
+
+    Ok(stats)
+}
+
+// compiles the shaders of materials that were added since the last frame
+fn load_shaders(game_state: &mut GameState) -> SystemResult {
+    let renderer = game_state.require_resource::<RenderResource>()?;
+
+    let mut missing: Vec<String> = Vec::new();
+    if game_state.conf.shadow_quality != ShadowQuality::Off
+        && renderer.shaders.needs_load(SHADOW_SHADER)
     {
-        let transform = entity.get_component::<Transform>(Transform::get_component_type());
-        let transform = if let Some(transform) = transform {
-            transform
-        } else {
+        missing.push(SHADOW_SHADER.to_string());
+    }
+
+    for material in game_state.get_components::<Material>(Material::get_component_type()) {
+        if renderer.shaders.needs_load(&material.shader) && !missing.contains(&material.shader) {
+            missing.push(material.shader.clone());
+        }
+    }
+
+    if game_state.get_resource::<Environment>().is_some()
+        && renderer.shaders.needs_load(SKYBOX_SHADER)
+    {
+        missing.push(SKYBOX_SHADER.to_string());
+    }
+
+    if game_state.get_resource::<DebugDraw>().is_some() && renderer.shaders.needs_load(DEBUG_SHADER)
+    {
+        missing.push(DEBUG_SHADER.to_string());
+    }
+
+    if renderer.default_font.is_some() && renderer.shaders.needs_load(TEXT_SHADER) {
+        missing.push(TEXT_SHADER.to_string());
+    }
+
+    if game_state.get_resource::<Ui>().is_some() && renderer.shaders.needs_load(UI_SHADER) {
+        missing.push(UI_SHADER.to_string());
+    }
+
+    for post_processing in
+        game_state.get_components::<PostProcessing>(PostProcessing::get_component_type())
+    {
+        let shaders = post_processing
+            .effects
+            .iter()
+            .flat_map(|effect| effect.shaders())
+            .chain([&COPY_SHADER]);
+        for shader in shaders {
+            if renderer.shaders.needs_load(shader) && !missing.iter().any(|name| name == shader) {
+                missing.push(shader.to_string());
+            }
+        }
+    }
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let RenderResource {
+        target, shaders, ..
+    } = game_state.require_resource_mut::<RenderResource>()?;
+
+    for name in missing {
+        if let Err(e) = shaders.load(RenderResource::target_facade(target), &name) {
+            eprintln!("Error loading shader {}: {}", name, e);
+        }
+    }
+
+    Ok(())
+}
+
+// active cameras in the order they're drawn
+fn render_cameras(game_state: &GameState) -> Vec<(&Entity, &Camera)> {
+    let mut cameras: Vec<(&Entity, &Camera)> = game_state
+        .get_entities_with::<Camera>(Camera::get_component_type())
+        .into_iter()
+        .filter_map(|entity| {
+            let camera = entity.get_component::<Camera>(Camera::get_component_type())?;
+            camera.active.then_some((entity, camera))
+        })
+        .collect();
+
+    cameras.sort_by_key(|(_, camera)| camera.order);
+    cameras
+}
+
+// (re)creates the post processing images for the viewport sizes of cameras that need them
+fn create_post_targets(game_state: &mut GameState) -> SystemResult {
+    let dimensions = game_state
+        .require_resource::<RenderResource>()?
+        .dimensions();
+    let mut needed: Vec<(u32, u32)> = Vec::new();
+    for (entity, camera) in render_cameras(game_state) {
+        let pixels = camera.viewport.to_pixels(dimensions);
+        let size = (pixels.width, pixels.height);
+
+        if entity
+            .get_component::<PostProcessing>(PostProcessing::get_component_type())
+            .is_some()
+            && !needed.contains(&size)
+        {
+            needed.push(size);
+        }
+    }
+
+    let renderer = game_state.require_resource_mut::<RenderResource>()?;
+    renderer
+        .post_targets
+        .retain(|targets| needed.contains(&targets.dimensions()));
+
+    for size in needed {
+        if !renderer
+            .post_targets
+            .iter()
+            .any(|targets| targets.dimensions() == size)
+        {
+            let targets = PostTargets::new(renderer.facade(), size)?;
+            renderer.post_targets.push(targets);
+        }
+    }
+
+    Ok(())
+}
+
+fn create_shadow_maps(game_state: &mut GameState) -> SystemResult {
+    let quality = game_state.conf.shadow_quality;
+    let renderer = game_state.require_resource_mut::<RenderResource>()?;
+
+    if quality == ShadowQuality::Off || renderer.shadow_maps.is_some() {
+        return Ok(());
+    }
+
+    renderer.shadow_maps = Some(ShadowMaps::new(renderer.facade(), quality)?);
+    Ok(())
+}
+
+// what every camera draws this frame
+struct Scene<'a> {
+    environment: Option<&'a Environment>,
+    debug: Option<&'a DebugDraw>,
+    // drawn over the finished frame
+    screen_text: &'a TextBatch<'a>,
+    // drawn over everything else
+    ui: Option<&'a Ui>,
+    // seconds of game time, for animated effects
+    time: f32,
+}
+
+// what one camera draws this frame
+struct View<'a> {
+    camera: &'a Camera,
+    post_processing: Option<&'a PostProcessing>,
+    // the camera's viewport in pixels
+    rect: glium::Rect,
+    lights: SceneLights,
+    batches: Batches<'a>,
+    // labels and debug text, drawn with the scene
+    world_text: TextBatch<'a>,
+}
+
+impl<'a> View<'a> {
+    fn new(
+        game_state: &'a GameState,
+        render_resource: &'a RenderResource,
+        entity: &'a Entity,
+        camera: &'a Camera,
+        dimensions: (u32, u32),
+        max_shadows: usize,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let rect = camera.viewport.to_pixels(dimensions);
+
+        Ok(View {
+            camera,
+            post_processing: entity
+                .get_component::<PostProcessing>(PostProcessing::get_component_type()),
+            rect,
+            lights: gather_lights(game_state, camera.get_world_position(), max_shadows),
+            batches: Batches::new(game_state, render_resource.facade(), camera)?,
+            world_text: gather_world_text(
+                game_state,
+                render_resource.default_font.as_ref(),
+                camera,
+                rect.height,
+            ),
+        })
+    }
+}
+
+// draw calls made for a frame
+#[derive(Default)]
+struct FrameStats {
+    draw_calls: usize,
+    shadow_draw_calls: usize,
+    post_passes: usize,
+}
+
+// every camera's view in its viewport with screen text and the ui over them
+fn draw_frame<S: Surface>(
+    render_resource: &RenderResource,
+    views: &[View],
+    scene: &Scene,
+    frame: &mut S,
+) -> Result<FrameStats, Box<dyn std::error::Error>> {
+    let mut stats = FrameStats::default();
+
+    for view in views {
+        if view.rect.width == 0 || view.rect.height == 0 {
             continue;
-        };
+        }
 
-        let model = entity.get_component::<Model>(Model::get_component_type());
-        let model = if let Some(model) = model {
-            model
+        // shadows follow the camera, so every view draws its own
+        if let Some(shadow_maps) = &render_resource.shadow_maps {
+            stats.shadow_draw_calls +=
+                shadow_maps.render(render_resource, &view.batches, &view.lights.shadow_casters)?;
+        }
+
+        let mut surface = ViewportSurface::new(frame, view.rect);
+        match view.camera.clear {
+            CameraClear::Color(color) => {
+                surface.clear_color_and_depth((color[0], color[1], color[2], color[3]), 1.0)
+            }
+            CameraClear::Depth => surface.clear_depth(1.0),
+            CameraClear::Nothing => (),
+        }
+
+        let (draw_calls, post_passes) = draw_view(render_resource, view, scene, &mut surface)?;
+        stats.draw_calls += draw_calls;
+        stats.post_passes += post_passes;
+    }
+
+    if let Some(program) = render_resource.shaders.get(TEXT_SHADER) {
+        let (width, height) = frame.get_dimensions();
+        stats.draw_calls += scene.screen_text.draw(
+            render_resource.facade(),
+            program,
+            screen_matrix(width, height),
+            frame,
+        )?;
+    }
+
+    if let (Some(ui), Some(program)) = (scene.ui, render_resource.shaders.get(UI_SHADER)) {
+        stats.draw_calls += ui.draw(render_resource, program, frame)?;
+    }
+
+    Ok(stats)
+}
+
+// draws the view into `frame`, through the camera's post processing if it has any. returns the
+// number of draw calls made for the scene and for post processing
+fn draw_view<S: Surface>(
+    render_resource: &RenderResource,
+    view: &View,
+    scene: &Scene,
+    frame: &mut S,
+) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let dimensions = frame.get_dimensions();
+    let targets = render_resource
+        .post_targets
+        .iter()
+        .find(|targets| targets.dimensions() == dimensions);
+    let (post_processing, targets) = match (view.post_processing, targets) {
+        (Some(post_processing), Some(targets)) => (post_processing, targets),
+        _ => {
+            let draw_calls = draw_batches(render_resource, view, scene, frame)?
+                + draw_debug(render_resource, view, scene, frame)?;
+            return Ok((draw_calls, 0));
+        }
+    };
+
+    // debug shapes need the scene's depth, so they go through the effects too
+    let mut framebuffer = targets.scene_framebuffer(render_resource.facade())?;
+    framebuffer.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
+    let draw_calls = draw_batches(render_resource, view, scene, &mut framebuffer)?
+        + draw_debug(render_resource, view, scene, &mut framebuffer)?;
+
+    let passes = targets.apply(
+        render_resource.facade(),
+        &render_resource.shaders,
+        &post_processing.effects,
+        scene.time,
+        frame,
+    )?;
+
+    Ok((draw_calls, passes))
+}
+
+// opaque objects, then the skybox, then transparent objects. returns the number of draw calls made
+fn draw_batches<S: Surface>(
+    render_resource: &RenderResource,
+    view: &View,
+    scene: &Scene,
+    frame: &mut S,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let View {
+        camera,
+        lights,
+        batches,
+        ..
+    } = view;
+    let (pcf_radius, shadow_texel_size) = match &render_resource.shadow_maps {
+        Some(shadow_maps) => (
+            shadow_maps.quality.pcf_radius(),
+            1.0 / shadow_maps.quality.resolution() as f32,
+        ),
+        None => (0, 0.0),
+    };
+
+    let view = camera.get_view();
+    let proj = camera.get_proj();
+    let camera_position = camera.get_world_position();
+
+    let mut draw_calls = 0;
+    let environment = scene.environment;
+
+    let mut skybox = environment.zip(render_resource.shaders.get(SKYBOX_SHADER));
+    for batch in batches
+        .batches
+        .iter()
+        .filter(|batch| !batch.visible.is_empty())
+    {
+        if batch.material.blend_mode.is_transparent() {
+            if let Some((environment, program)) = skybox.take() {
+                environment.draw_skybox(program, camera, frame)?;
+                draw_calls += 1;
+            }
+        }
+
+        // shaders that failed to compile are reported once by `load_shaders`
+        let program = if let Some(program) = render_resource.shaders.get(&batch.material.shader) {
+            program
         } else {
             continue;
         };
 
-        let texture = entity.get_component::<Texture>(Texture::get_component_type());
-        let texture = if let Some(texture) = texture {
-            texture
-        } else {
-            continue;
+        let globals = [
+            ("view", MaterialValue::Mat4(view)),
+            ("proj", MaterialValue::Mat4(proj)),
+            ("camera_position", MaterialValue::Vec3(camera_position)),
+            ("pcf_radius", MaterialValue::Int(pcf_radius)),
+            ("shadow_texel_size", MaterialValue::Float(shadow_texel_size)),
+            (
+                "alpha_cutoff",
+                MaterialValue::Float(batch.material.blend_mode.alpha_cutoff()),
+            ),
+        ];
+        let uniforms = MaterialUniforms {
+            material: batch.material,
+            globals: &globals,
+            scene: &lights.values,
+            shadow_maps: render_resource.shadow_maps.as_ref(),
+            environment: Some(environment.unwrap_or(&render_resource.default_environment)),
+            default_textures: &render_resource.default_textures,
         };
 
-        let sampler = texture.sampler;
+        batches.draw(
+            batch,
+            batch.visible.clone(),
+            frame,
+            program,
+            &uniforms,
+            &batch
+                .material
+                .blend_mode
+                .draw_parameters(&render_resource.params),
+        )?;
+        draw_calls += 1;
+    }
 
-        let uniforms = uniform! {
-            model: transform.get_model().0,
-            view: camera.get_view(),
-            proj: camera.get_proj(),
+    // when there were no transparent objects
+    if let Some((environment, program)) = skybox {
+        environment.draw_skybox(program, camera, frame)?;
+        draw_calls += 1;
+    }
 
-            texture: sampler,
-            ambient: 0.3f32,
-            light: normalize([-1.0, 5.0, 0.9]),
-        };
+    Ok(draw_calls)
+}
 
-        if let Err(e) = frame.draw(
-            &model.vertices,
-            &model.indices,
-            &render_resource.program,
-            &uniforms,
-            &render_resource.params,
-        ) {
-            eprintln!("Error drawing frame: {}", e.to_string());
-            return;
+// debug shapes and world text
+fn draw_debug<S: Surface>(
+    render_resource: &RenderResource,
+    view: &View,
+    scene: &Scene,
+    frame: &mut S,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let camera = view.camera;
+    let mut draw_calls = match (scene.debug, render_resource.shaders.get(DEBUG_SHADER)) {
+        (Some(debug), Some(program)) => {
+            debug.draw(render_resource.facade(), program, camera, frame)?
         }
-    }
+        _ => 0,
+    };
 
-    if let Err(e) = frame.finish() {
-        eprintln!("Error finishing frame: {}", e.to_string());
+    if let Some(program) = render_resource.shaders.get(TEXT_SHADER) {
+        // matrices multiply in reverse, this is `proj * view`
+        let matrix = Matrix4(camera.get_view()) * Matrix4(camera.get_proj());
+        draw_calls += view
+            .world_text
+            .draw(render_resource.facade(), program, matrix.0, frame)?;
     }
+
+    Ok(draw_calls)
 }
@@ -0,0 +1,353 @@
+use crate::*;
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::texture::Texture2d;
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, Sampler, SamplerWrapFunction};
+use glium::Surface;
+use std::any::Any;
+
+// draws the final image when there are no effects
+pub const COPY_SHADER: &str = "post/copy";
+const EXPOSURE_SHADER: &str = "post/exposure";
+const BRIGHT_SHADER: &str = "post/bright";
+const BLUR_SHADER: &str = "post/blur";
+const BLOOM_SHADER: &str = "post/bloom";
+const TONEMAP_SHADER: &str = "post/tonemap";
+const FXAA_SHADER: &str = "post/fxaa";
+const VIGNETTE_SHADER: &str = "post/vignette";
+const FILM_GRAIN_SHADER: &str = "post/film_grain";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tonemapper {
+    Reinhard,
+    Aces,
+}
+
+// full screen effects, applied in order to the camera's HDR image
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostEffect {
+    // multiplies the color, before tonemapping
+    Exposure(f32),
+    // blurs whatever is brighter than `threshold` over its surroundings, more iterations spread it
+    // further
+    Bloom {
+        threshold: f32,
+        intensity: f32,
+        iterations: u32,
+    },
+    // maps HDR colors into 0..1, effects after it work on LDR colors
+    Tonemap(Tonemapper),
+    // smooths aliased edges, belongs after tonemapping
+    Fxaa,
+    // darkens the corners, starting `radius` of the way from the centre
+    Vignette {
+        intensity: f32,
+        radius: f32,
+    },
+    FilmGrain {
+        intensity: f32,
+    },
+}
+
+impl PostEffect {
+    pub fn shaders(&self) -> &'static [&'static str] {
+        match self {
+            PostEffect::Exposure(_) => &[EXPOSURE_SHADER],
+            PostEffect::Bloom { .. } => &[BRIGHT_SHADER, BLUR_SHADER, BLOOM_SHADER],
+            PostEffect::Tonemap(_) => &[TONEMAP_SHADER],
+            PostEffect::Fxaa => &[FXAA_SHADER],
+            PostEffect::Vignette { .. } => &[VIGNETTE_SHADER],
+            PostEffect::FilmGrain { .. } => &[FILM_GRAIN_SHADER],
+        }
+    }
+}
+
+// component type 18
+// on a camera's entity, renders that camera into an HDR image and runs these effects over it
+#[derive(Debug, Clone, Default)]
+pub struct PostProcessing {
+    pub effects: Vec<PostEffect>,
+}
+impl_component!(PostProcessing, 18);
+
+impl PostProcessing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_effect(mut self, effect: PostEffect) -> Self {
+        self.effects.push(effect);
+        self
+    }
+
+    // the game's grainy handheld camcorder look
+    pub fn found_footage() -> Self {
+        PostProcessing::new()
+            .with_effect(PostEffect::Exposure(1.2))
+            .with_effect(PostEffect::Bloom {
+                threshold: 1.0,
+                intensity: 0.6,
+                iterations: 3,
+            })
+            .with_effect(PostEffect::Tonemap(Tonemapper::Aces))
+            .with_effect(PostEffect::Fxaa)
+            .with_effect(PostEffect::Vignette {
+                intensity: 0.5,
+                radius: 0.4,
+            })
+            .with_effect(PostEffect::FilmGrain { intensity: 0.08 })
+    }
+}
+
+// offscreen images for post processing, sized like the render target
+#[derive(Debug)]
+pub struct PostTargets {
+    // the scene is drawn into the first, effects alternate between them
+    pub color: [Texture2d; 2],
+    pub depth: glium::texture::DepthTexture2d,
+    // half resolution, for blurring bloom
+    pub bloom: [Texture2d; 2],
+}
+
+impl PostTargets {
+    pub fn new(
+        facade: &dyn glium::backend::Facade,
+        dimensions: (u32, u32),
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let hdr = |width: u32, height: u32| {
+            Texture2d::empty_with_format(
+                facade,
+                glium::texture::UncompressedFloatFormat::F16F16F16F16,
+                glium::texture::MipmapsOption::NoMipmap,
+                width.max(1),
+                height.max(1),
+            )
+        };
+        let (width, height) = dimensions;
+
+        Ok(PostTargets {
+            color: [hdr(width, height)?, hdr(width, height)?],
+            depth: glium::texture::DepthTexture2d::empty_with_format(
+                facade,
+                glium::texture::DepthFormat::I24,
+                glium::texture::MipmapsOption::NoMipmap,
+                width.max(1),
+                height.max(1),
+            )?,
+            bloom: [hdr(width / 2, height / 2)?, hdr(width / 2, height / 2)?],
+        })
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.depth.dimensions()
+    }
+
+    // where the scene is drawn before the effects run
+    pub fn scene_framebuffer<'a>(
+        &'a self,
+        facade: &dyn glium::backend::Facade,
+    ) -> Result<SimpleFrameBuffer<'a>, Box<dyn std::error::Error>> {
+        Ok(SimpleFrameBuffer::with_depth_buffer(
+            facade,
+            &self.color[0],
+            &self.depth,
+        )?)
+    }
+
+    // runs `effects` over the scene, the last one drawing into `frame`, and returns the number of
+    // passes drawn. effects whose shaders aren't compiled are skipped
+    pub fn apply<S: Surface>(
+        &self,
+        facade: &dyn glium::backend::Facade,
+        shaders: &ShaderCache,
+        effects: &[PostEffect],
+        time: f32,
+        frame: &mut S,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let effects: Vec<&PostEffect> = effects
+            .iter()
+            .filter(|effect| {
+                effect
+                    .shaders()
+                    .iter()
+                    .all(|name| shaders.get(name).is_some())
+            })
+            .collect();
+
+        if effects.is_empty() {
+            let program = shaders.get(COPY_SHADER).ok_or("Copy shader isn't loaded")?;
+            let uniforms = glium::uniform! { source: sampled(&self.color[0]) };
+            draw_fullscreen(frame, program, &uniforms, &Default::default())?;
+            return Ok(1);
+        }
+
+        let mut passes = 0;
+        let mut source = 0;
+        for (i, effect) in effects.iter().enumerate() {
+            if i + 1 == effects.len() {
+                passes += self.apply_effect(facade, shaders, effect, source, time, frame)?;
+            } else {
+                let mut framebuffer = SimpleFrameBuffer::new(facade, &self.color[1 - source])?;
+                passes +=
+                    self.apply_effect(facade, shaders, effect, source, time, &mut framebuffer)?;
+                source = 1 - source;
+            }
+        }
+
+        Ok(passes)
+    }
+
+    fn apply_effect<S: Surface>(
+        &self,
+        facade: &dyn glium::backend::Facade,
+        shaders: &ShaderCache,
+        effect: &PostEffect,
+        source: usize,
+        time: f32,
+        target: &mut S,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let program = |name: &str| {
+            shaders
+                .get(name)
+                .ok_or_else(|| format!("Shader {} isn't loaded", name))
+        };
+        let source = sampled(&self.color[source]);
+
+        match *effect {
+            PostEffect::Exposure(exposure) => {
+                let uniforms = glium::uniform! { source: source, exposure: exposure };
+                draw_fullscreen(
+                    target,
+                    program(EXPOSURE_SHADER)?,
+                    &uniforms,
+                    &Default::default(),
+                )?;
+                Ok(1)
+            }
+            PostEffect::Bloom {
+                threshold,
+                intensity,
+                iterations,
+            } => {
+                let uniforms = glium::uniform! { source: source, threshold: threshold };
+                let mut framebuffer = SimpleFrameBuffer::new(facade, &self.bloom[0])?;
+                draw_fullscreen(
+                    &mut framebuffer,
+                    program(BRIGHT_SHADER)?,
+                    &uniforms,
+                    &Default::default(),
+                )?;
+
+                let (width, height) = self.bloom[0].dimensions();
+                let directions = [[1.0 / width as f32, 0.0], [0.0, 1.0 / height as f32]];
+                for _ in 0..iterations.max(1) {
+                    for (i, direction) in directions.into_iter().enumerate() {
+                        let uniforms = glium::uniform! {
+                            source: sampled(&self.bloom[i]),
+                            direction: direction,
+                        };
+                        let mut framebuffer = SimpleFrameBuffer::new(facade, &self.bloom[1 - i])?;
+                        draw_fullscreen(
+                            &mut framebuffer,
+                            program(BLUR_SHADER)?,
+                            &uniforms,
+                            &Default::default(),
+                        )?;
+                    }
+                }
+
+                let uniforms = glium::uniform! {
+                    source: source,
+                    bloom_map: sampled(&self.bloom[0]),
+                    intensity: intensity,
+                };
+                draw_fullscreen(
+                    target,
+                    program(BLOOM_SHADER)?,
+                    &uniforms,
+                    &Default::default(),
+                )?;
+                Ok(2 + 2 * iterations.max(1) as usize)
+            }
+            PostEffect::Tonemap(tonemapper) => {
+                let uniforms = glium::uniform! {
+                    source: source,
+                    tonemapper: tonemapper as i32,
+                };
+                draw_fullscreen(
+                    target,
+                    program(TONEMAP_SHADER)?,
+                    &uniforms,
+                    &Default::default(),
+                )?;
+                Ok(1)
+            }
+            PostEffect::Fxaa => {
+                let (width, height) = self.dimensions();
+                let uniforms = glium::uniform! {
+                    source: source,
+                    texel_size: [1.0 / width as f32, 1.0 / height as f32],
+                };
+                draw_fullscreen(
+                    target,
+                    program(FXAA_SHADER)?,
+                    &uniforms,
+                    &Default::default(),
+                )?;
+                Ok(1)
+            }
+            PostEffect::Vignette { intensity, radius } => {
+                let uniforms = glium::uniform! {
+                    source: source,
+                    intensity: intensity,
+                    radius: radius,
+                };
+                draw_fullscreen(
+                    target,
+                    program(VIGNETTE_SHADER)?,
+                    &uniforms,
+                    &Default::default(),
+                )?;
+                Ok(1)
+            }
+            PostEffect::FilmGrain { intensity } => {
+                let uniforms = glium::uniform! {
+                    source: source,
+                    intensity: intensity,
+                    time: time,
+                };
+                draw_fullscreen(
+                    target,
+                    program(FILM_GRAIN_SHADER)?,
+                    &uniforms,
+                    &Default::default(),
+                )?;
+                Ok(1)
+            }
+        }
+    }
+}
+
+fn sampled(texture: &Texture2d) -> Sampler<'_, Texture2d> {
+    texture
+        .sampled()
+        .magnify_filter(MagnifySamplerFilter::Linear)
+        .minify_filter(MinifySamplerFilter::Linear)
+        .wrap_function(SamplerWrapFunction::Clamp)
+}
+
+// a single triangle covering the target, the vertex shader makes it from `gl_VertexID`
+pub(crate) fn draw_fullscreen<S: Surface, U: glium::uniforms::Uniforms>(
+    target: &mut S,
+    program: &glium::Program,
+    uniforms: &U,
+    params: &glium::DrawParameters,
+) -> Result<(), Box<dyn std::error::Error>> {
+    target.draw(
+        glium::vertex::EmptyVertexAttributes { len: 3 },
+        glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+        program,
+        uniforms,
+        params,
+    )?;
+    Ok(())
+}
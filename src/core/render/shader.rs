@@ -0,0 +1,177 @@
+use crate::*;
+use glium::backend::Facade;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+// `<name>.vert` and `<name>.frag` in here make up the shader called `<name>`, a shader without its
+// own `.vert` uses the `default.vert` next to it
+pub const SHADER_DIR: &str = "assets/shaders";
+
+// seconds between checks for edited shader files
+const RELOAD_INTERVAL: f64 = 0.5;
+
+// compiled programs by shader name, each shader is only compiled once unless its files change
+#[derive(Debug)]
+pub struct ShaderCache {
+    programs: HashMap<String, glium::Program>,
+    // shaders that failed to compile, kept so the error is only reported once
+    failed: HashSet<String>,
+    // newest modification time of each shader's files when it was last compiled
+    modified: HashMap<String, Option<SystemTime>>,
+    pub reload_timer: Timer,
+}
+
+impl Default for ShaderCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShaderCache {
+    pub fn new() -> ShaderCache {
+        ShaderCache {
+            programs: HashMap::new(),
+            failed: HashSet::new(),
+            modified: HashMap::new(),
+            reload_timer: Timer::repeating(RELOAD_INTERVAL),
+        }
+    }
+
+    pub fn source_paths(name: &str) -> (PathBuf, PathBuf) {
+        let dir = PathBuf::from(SHADER_DIR);
+        let fragment = dir.join(format!("{}.frag", name));
+
+        let mut vertex = dir.join(format!("{}.vert", name));
+        if !vertex.exists() {
+            vertex = fragment.with_file_name("default.vert");
+        }
+
+        (vertex, fragment)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&glium::Program> {
+        self.programs.get(name)
+    }
+
+    // whether `load` still has to be called for this shader
+    pub fn needs_load(&self, name: &str) -> bool {
+        !self.programs.contains_key(name) && !self.failed.contains(name)
+    }
+
+    pub fn load(
+        &mut self,
+        facade: &dyn Facade,
+        name: &str,
+    ) -> Result<&glium::Program, Box<dyn std::error::Error>> {
+        self.modified
+            .insert(name.to_string(), Self::sources_modified(name));
+
+        let program = match Self::compile(facade, name) {
+            Ok(program) => program,
+            Err(e) => {
+                self.failed.insert(name.to_string());
+                return Err(e);
+            }
+        };
+
+        self.failed.remove(name);
+        Ok(self.programs.entry(name.to_string()).or_insert(program))
+    }
+
+    // recompiles every shader whose files changed since it was last compiled, a shader that fails
+    // to compile keeps its previous program
+    pub fn reload_changed(&mut self, facade: &dyn Facade) {
+        let changed: Vec<String> = self
+            .modified
+            .iter()
+            .filter(|(name, modified)| Self::sources_modified(name) != **modified)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        for name in changed {
+            self.modified
+                .insert(name.clone(), Self::sources_modified(&name));
+
+            match Self::compile(facade, &name) {
+                Ok(program) => {
+                    self.programs.insert(name.clone(), program);
+                    self.failed.remove(&name);
+                    println!("Reloaded shader {}", name);
+                }
+                Err(e) => eprintln!("Error reloading shader {}: {}", name, e),
+            }
+        }
+    }
+
+    fn sources_modified(name: &str) -> Option<SystemTime> {
+        let (vertex_path, fragment_path) = Self::source_paths(name);
+
+        let vertex = std::fs::metadata(vertex_path)
+            .and_then(|m| m.modified())
+            .ok()?;
+        let fragment = std::fs::metadata(fragment_path)
+            .and_then(|m| m.modified())
+            .ok()?;
+
+        Some(vertex.max(fragment))
+    }
+
+    // adds the engine's limits as defines after the `#version` line, `#line` keeps the line
+    // numbers in compiler errors matching the file
+    fn with_defines(source: &str) -> String {
+        let (version, rest) = match source.split_once('\n') {
+            Some((first, rest)) if first.trim_start().starts_with("#version") => (first, rest),
+            _ => return source.to_string(),
+        };
+
+        format!(
+            "{}\n#define MAX_DIRECTIONAL_LIGHTS {}\n#define MAX_POINT_LIGHTS {}\n\
+             #define MAX_SPOT_LIGHTS {}\n#define MAX_SHADOW_MAPS {}\n#line 2\n{}",
+            version,
+            MAX_DIRECTIONAL_LIGHTS,
+            MAX_POINT_LIGHTS,
+            MAX_SPOT_LIGHTS,
+            MAX_SHADOW_MAPS,
+            rest
+        )
+    }
+
+    fn compile(
+        facade: &dyn Facade,
+        name: &str,
+    ) -> Result<glium::Program, Box<dyn std::error::Error>> {
+        let (vertex_path, fragment_path) = Self::source_paths(name);
+
+        let read = |path: &PathBuf| {
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("Error reading {}: {}", path.display(), e))
+        };
+        let vertex_source = Self::with_defines(&read(&vertex_path)?);
+        let fragment_source = Self::with_defines(&read(&fragment_path)?);
+
+        Ok(glium::Program::from_source(
+            facade,
+            &vertex_source,
+            &fragment_source,
+            None,
+        )?)
+    }
+}
+
+create_system!(reload_shaders, get_reload_shaders_system;
+    uses RenderResource, Time);
+async fn reload_shaders(game_state: &mut GameState, _t: f64, dt: f64) -> SystemResult {
+    let dt = real_delta_or(game_state, dt);
+
+    let RenderResource {
+        target, shaders, ..
+    } = game_state.require_resource_mut::<RenderResource>()?;
+    shaders.reload_timer.tick(dt);
+
+    if shaders.reload_timer.just_finished() {
+        shaders.reload_changed(RenderResource::target_facade(target));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use crate::*;
+use glium::Surface;
+use serde::{Deserialize, Serialize};
+
+// directional and spot lights share this many shadow maps, lights past it are unshadowed
+pub const MAX_SHADOW_MAPS: usize = 4;
+
+// shader used to draw the scene into shadow maps
+pub const SHADOW_SHADER: &str = "shadow";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum ShadowQuality {
+    Off,
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl ShadowQuality {
+    pub fn resolution(&self) -> u32 {
+        match self {
+            ShadowQuality::Off => 0,
+            ShadowQuality::Low => 512,
+            ShadowQuality::Medium => 1024,
+            ShadowQuality::High => 2048,
+        }
+    }
+
+    // percentage closer filtering taps on each side of the sample, 0 only uses the hardware's
+    // bilinear comparison
+    pub fn pcf_radius(&self) -> i32 {
+        match self {
+            ShadowQuality::Off | ShadowQuality::Low => 0,
+            ShadowQuality::Medium => 1,
+            ShadowQuality::High => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShadowSettings {
+    // pushes the compared depth towards the light to stop surfaces shadowing themselves
+    pub bias: f32,
+    // half the width of the square a directional light's shadow covers, centred on the camera
+    pub extent: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        ShadowSettings {
+            bias: 0.002,
+            extent: 20.0,
+        }
+    }
+}
+
+// a light that got a shadow map this frame
+#[derive(Debug, Clone)]
+pub struct ShadowCaster {
+    // world space to the light's clip space
+    pub matrix: Matrix4,
+    pub bias: f32,
+}
+
+impl ShadowCaster {
+    pub fn directional(direction: [f32; 3], center: [f32; 3], settings: &ShadowSettings) -> Self {
+        let direction = normalize(direction);
+        let distance = settings.extent * 2.0;
+        let eye = [
+            center[0] - direction[0] * distance,
+            center[1] - direction[1] * distance,
+            center[2] - direction[2] * distance,
+        ];
+
+        let view = Matrix4::look_at(eye, center, up_for(direction));
+        let proj = Matrix4::orthographic(
+            settings.extent,
+            settings.extent,
+            0.0,
+            distance + settings.extent,
+        );
+
+        ShadowCaster {
+            // matrices multiply in reverse, this is `proj * view` in the shader
+            matrix: view * proj,
+            bias: settings.bias,
+        }
+    }
+
+    pub fn spot(
+        position: [f32; 3],
+        direction: [f32; 3],
+        outer_angle: f32,
+        range: f32,
+        settings: &ShadowSettings,
+    ) -> Self {
+        let direction = normalize(direction);
+        let target = [
+            position[0] + direction[0],
+            position[1] + direction[1],
+            position[2] + direction[2],
+        ];
+
+        let view = Matrix4::look_at(position, target, up_for(direction));
+        let proj = Matrix4::perspective((outer_angle * 2.0).to_radians(), 1.0, 0.05, range);
+
+        ShadowCaster {
+            matrix: view * proj,
+            bias: settings.bias,
+        }
+    }
+}
+
+// any up vector works as long as it isn't parallel to the view direction
+fn up_for(direction: [f32; 3]) -> [f32; 3] {
+    if direction[1].abs() > 0.99 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 1.0, 0.0]
+    }
+}
+
+#[derive(Debug)]
+pub struct ShadowMaps {
+    pub quality: ShadowQuality,
+    // one layer per shadow caster
+    pub depth: glium::texture::DepthTexture2dArray,
+}
+
+impl ShadowMaps {
+    pub fn new(
+        facade: &dyn glium::backend::Facade,
+        quality: ShadowQuality,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let depth = glium::texture::DepthTexture2dArray::empty_with_format(
+            facade,
+            glium::texture::DepthFormat::I24,
+            glium::texture::MipmapsOption::NoMipmap,
+            quality.resolution(),
+            quality.resolution(),
+            MAX_SHADOW_MAPS as u32,
+        )?;
+
+        Ok(ShadowMaps { quality, depth })
+    }
+
+    // compares against the stored depth when sampled, giving 1 where lit
+    pub fn sampler(&self) -> glium::uniforms::SamplerBehavior {
+        glium::uniforms::SamplerBehavior {
+            magnify_filter: glium::uniforms::MagnifySamplerFilter::Linear,
+            minify_filter: glium::uniforms::MinifySamplerFilter::Linear,
+            wrap_function: (
+                glium::uniforms::SamplerWrapFunction::Clamp,
+                glium::uniforms::SamplerWrapFunction::Clamp,
+                glium::uniforms::SamplerWrapFunction::Clamp,
+            ),
+            depth_texture_comparison: Some(glium::uniforms::DepthTextureComparison::LessOrEqual),
+            ..Default::default()
+        }
+    }
+
+    // draws every batch into the casters' layers, returning the number of draw calls made
+    pub fn render(
+        &self,
+        render_resource: &RenderResource,
+        batches: &Batches,
+        casters: &[ShadowCaster],
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let program = match render_resource.shaders.get(SHADOW_SHADER) {
+            Some(program) => program,
+            None => return Ok(0),
+        };
+
+        let params = glium::DrawParameters {
+            depth: glium::Depth {
+                test: glium::draw_parameters::DepthTest::IfLess,
+                write: true,
+                ..Default::default()
+            },
+            // back faces are further from the light, which hides most self shadowing
+            backface_culling: glium::draw_parameters::BackfaceCullingMode::CullCounterClockwise,
+            ..Default::default()
+        };
+
+        let mut draw_calls = 0;
+        for (layer, caster) in casters.iter().enumerate() {
+            let layer = self
+                .depth
+                .layer(layer as u32)
+                .ok_or("Shadow map layer out of range")?;
+            let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::depth_only(
+                render_resource.facade(),
+                layer.main_level(),
+            )?;
+            framebuffer.clear_depth(1.0);
+
+            // blended objects don't cast shadows
+            for batch in batches
+                .batches
+                .iter()
+                .filter(|batch| !batch.material.blend_mode.is_transparent())
+            {
+                let globals = [
+                    ("light_matrix", MaterialValue::Mat4(caster.matrix.0)),
+                    (
+                        "alpha_cutoff",
+                        MaterialValue::Float(batch.material.blend_mode.alpha_cutoff()),
+                    ),
+                ];
+                let uniforms = MaterialUniforms {
+                    material: batch.material,
+                    globals: &globals,
+                    scene: &[],
+                    shadow_maps: None,
+                    environment: None,
+                    default_textures: &render_resource.default_textures,
+                };
+
+                batches.draw(
+                    batch,
+                    batch.instances.clone(),
+                    &mut framebuffer,
+                    program,
+                    &uniforms,
+                    &params,
+                )?;
+                draw_calls += 1;
+            }
+        }
+
+        Ok(draw_calls)
+    }
+}
@@ -0,0 +1,582 @@
+use crate::*;
+use ab_glyph::{Font as _, FontVec, GlyphId, PxScale, ScaleFont};
+use glium::backend::Facade;
+use glium::texture::Texture2d;
+use glium::Surface;
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+// draws glyphs from a font's atlas
+pub const TEXT_SHADER: &str = "text";
+// used by text that doesn't set a font
+pub const DEFAULT_FONT: &str = "assets/fonts/DejaVuSansMono.ttf";
+
+// glyphs are rasterized once at this size and scaled to every other size
+const ATLAS_FONT_SIZE: f32 = 48.0;
+const ATLAS_WIDTH: usize = 1024;
+// empty texels around every glyph, so filtering doesn't bleed its neighbours into it
+const GLYPH_PADDING: usize = 2;
+// printable ASCII and Latin-1, anything else is drawn as `FALLBACK`
+const CHARACTERS: [RangeInclusive<char>; 2] = [' '..='~', '\u{a1}'..='\u{ff}'];
+const FALLBACK: char = '?';
+
+// debug labels stay this many pixels tall however far away they are
+const DEBUG_LABEL_SIZE: f32 = 16.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+// where a glyph is in the atlas, distances are in pixels at `ATLAS_FONT_SIZE`
+#[derive(Debug, Clone, Copy)]
+struct Glyph {
+    id: GlyphId,
+    // from the pen position on the baseline to the top left of the glyph's image
+    offset: [f32; 2],
+    size: [f32; 2],
+    uv_min: [f32; 2],
+    uv_max: [f32; 2],
+    advance: f32,
+}
+
+// a glyph's quad after layout, in the units the text was laid out in with y pointing down
+#[derive(Debug, Clone, Copy)]
+pub struct GlyphQuad {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+}
+
+#[derive(Debug)]
+pub struct Font {
+    font: FontVec,
+    // coverage of every glyph in `CHARACTERS`, in the red channel
+    pub atlas: Texture2d,
+    glyphs: HashMap<char, Glyph>,
+    ascent: f32,
+    line_height: f32,
+    line_gap: f32,
+}
+
+impl Font {
+    pub fn load(path: &str, facade: &dyn Facade) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_bytes(std::fs::read(path)?, facade)
+    }
+
+    // the contents of a TTF or OTF file
+    pub fn from_bytes(
+        bytes: Vec<u8>,
+        facade: &dyn Facade,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let font = FontVec::try_from_vec(bytes)?;
+        let scaled = font.as_scaled(PxScale::from(ATLAS_FONT_SIZE));
+
+        // glyphs are packed into rows, the atlas grows downwards to fit them
+        let mut pixels: Vec<u8> = Vec::new();
+        let mut rects = Vec::new();
+        let (mut x, mut y, mut row_height) = (GLYPH_PADDING, GLYPH_PADDING, 0);
+
+        for c in CHARACTERS.into_iter().flatten() {
+            let id = font.glyph_id(c);
+            // the font doesn't have it
+            if id.0 == 0 {
+                continue;
+            }
+
+            let advance = scaled.h_advance(id);
+            let Some(outline) = scaled.outline_glyph(id.with_scale(ATLAS_FONT_SIZE)) else {
+                // nothing to draw, like a space
+                rects.push((c, id, [0.0; 2], [0; 2], [0; 2], advance));
+                continue;
+            };
+
+            let bounds = outline.px_bounds();
+            let (width, height) = (bounds.width() as usize, bounds.height() as usize);
+            if x + width + GLYPH_PADDING > ATLAS_WIDTH {
+                x = GLYPH_PADDING;
+                y += row_height + GLYPH_PADDING;
+                row_height = 0;
+            }
+
+            let needed = (y + height + GLYPH_PADDING) * ATLAS_WIDTH;
+            if pixels.len() < needed {
+                pixels.resize(needed, 0);
+            }
+            outline.draw(|gx, gy, coverage| {
+                let index = (y + gy as usize) * ATLAS_WIDTH + x + gx as usize;
+                pixels[index] = (coverage.clamp(0.0, 1.0) * 255.0).round() as u8;
+            });
+
+            rects.push((
+                c,
+                id,
+                [bounds.min.x, bounds.min.y],
+                [x, y],
+                [width, height],
+                advance,
+            ));
+            x += width + GLYPH_PADDING;
+            row_height = row_height.max(height);
+        }
+
+        let atlas_height = (y + row_height + GLYPH_PADDING).next_power_of_two();
+        pixels.resize(atlas_height * ATLAS_WIDTH, 0);
+
+        let glyphs = rects
+            .into_iter()
+            .map(|(c, id, offset, position, size, advance)| {
+                let uv = |x: usize, y: usize| {
+                    [
+                        x as f32 / ATLAS_WIDTH as f32,
+                        y as f32 / atlas_height as f32,
+                    ]
+                };
+                let glyph = Glyph {
+                    id,
+                    offset,
+                    size: [size[0] as f32, size[1] as f32],
+                    uv_min: uv(position[0], position[1]),
+                    uv_max: uv(position[0] + size[0], position[1] + size[1]),
+                    advance,
+                };
+                (c, glyph)
+            })
+            .collect();
+
+        // rows are uploaded top first, so v grows downwards like the layout's y
+        let image = glium::texture::RawImage2d {
+            data: Cow::Owned(pixels),
+            width: ATLAS_WIDTH as u32,
+            height: atlas_height as u32,
+            format: glium::texture::ClientFormat::U8,
+        };
+        let atlas = Texture2d::with_format(
+            facade,
+            image,
+            glium::texture::UncompressedFloatFormat::U8,
+            glium::texture::MipmapsOption::AutoGeneratedMipmaps,
+        )?;
+
+        Ok(Font {
+            ascent: scaled.ascent(),
+            line_height: scaled.height() + scaled.line_gap(),
+            line_gap: scaled.line_gap(),
+            font,
+            atlas,
+            glyphs,
+        })
+    }
+
+    fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c).or_else(|| self.glyphs.get(&FALLBACK))
+    }
+
+    fn line_width(&self, line: &str) -> f32 {
+        let scaled = self.font.as_scaled(PxScale::from(ATLAS_FONT_SIZE));
+        let mut width = 0.0;
+        let mut previous: Option<GlyphId> = None;
+
+        for glyph in line.chars().filter_map(|c| self.glyph(c)) {
+            if let Some(previous) = previous {
+                width += scaled.kern(previous, glyph.id);
+            }
+            width += glyph.advance;
+            previous = Some(glyph.id);
+        }
+
+        width
+    }
+
+    // the width and height of `text` drawn at `size`, lines are split at '\n'
+    pub fn measure(&self, text: &str, size: f32) -> [f32; 2] {
+        let scale = size / ATLAS_FONT_SIZE;
+        let width = text
+            .split('\n')
+            .map(|line| self.line_width(line))
+            .fold(0.0, f32::max);
+        let lines = text.split('\n').count() as f32;
+
+        [
+            width * scale,
+            (lines * self.line_height - self.line_gap) * scale,
+        ]
+    }
+
+    // quads for every glyph of `text` drawn at `size`, around the point given by the alignment
+    pub fn layout(
+        &self,
+        text: &str,
+        size: f32,
+        align: TextAlign,
+        vertical_align: VerticalAlign,
+    ) -> Vec<GlyphQuad> {
+        let scaled = self.font.as_scaled(PxScale::from(ATLAS_FONT_SIZE));
+        let scale = size / ATLAS_FONT_SIZE;
+
+        let height = self.measure(text, ATLAS_FONT_SIZE)[1];
+        let top = match vertical_align {
+            VerticalAlign::Top => 0.0,
+            VerticalAlign::Middle => -height / 2.0,
+            VerticalAlign::Bottom => -height,
+        };
+
+        let mut quads = Vec::new();
+        for (i, line) in text.split('\n').enumerate() {
+            let width = self.line_width(line);
+            let mut x = match align {
+                TextAlign::Left => 0.0,
+                TextAlign::Center => -width / 2.0,
+                TextAlign::Right => -width,
+            };
+            let baseline = top + self.ascent + i as f32 * self.line_height;
+
+            let mut previous: Option<GlyphId> = None;
+            for glyph in line.chars().filter_map(|c| self.glyph(c)) {
+                if let Some(previous) = previous {
+                    x += scaled.kern(previous, glyph.id);
+                }
+
+                if glyph.size[0] > 0.0 {
+                    let min = [x + glyph.offset[0], baseline + glyph.offset[1]];
+                    quads.push(GlyphQuad {
+                        min: [min[0] * scale, min[1] * scale],
+                        max: [
+                            (min[0] + glyph.size[0]) * scale,
+                            (min[1] + glyph.size[1]) * scale,
+                        ],
+                        uv_min: glyph.uv_min,
+                        uv_max: glyph.uv_max,
+                    });
+                }
+
+                x += glyph.advance;
+                previous = Some(glyph.id);
+            }
+        }
+
+        quads
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextSpace {
+    // pixels from the top left of the window, `size` is in pixels
+    Screen([f32; 2]),
+    // offset from the entity's `Transform` position and always facing the camera, `size` is in
+    // world units
+    World([f32; 3]),
+}
+
+// component type 21
+#[derive(Debug, Clone)]
+pub struct Text {
+    pub text: String,
+    // `DEFAULT_FONT` when `None`
+    pub font: Option<Rc<Font>>,
+    pub size: f32,
+    pub color: [f32; 4],
+    pub align: TextAlign,
+    pub vertical_align: VerticalAlign,
+    pub space: TextSpace,
+    // world text is hidden behind the scene when set, drawn over everything otherwise
+    pub depth_test: bool,
+}
+impl_component!(Text, 21);
+
+impl Text {
+    pub fn screen(text: &str, position: [f32; 2], size: f32) -> Self {
+        Text {
+            text: text.to_string(),
+            font: None,
+            size,
+            color: [1.0; 4],
+            align: TextAlign::Left,
+            vertical_align: VerticalAlign::Top,
+            space: TextSpace::Screen(position),
+            depth_test: false,
+        }
+    }
+
+    // a label centred above the entity
+    pub fn world(text: &str, offset: [f32; 3], size: f32) -> Self {
+        Text {
+            align: TextAlign::Center,
+            vertical_align: VerticalAlign::Bottom,
+            space: TextSpace::World(offset),
+            depth_test: true,
+            ..Self::screen(text, [0.0; 2], size)
+        }
+    }
+
+    pub fn with_font(mut self, font: Rc<Font>) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_align(mut self, align: TextAlign, vertical_align: VerticalAlign) -> Self {
+        self.align = align;
+        self.vertical_align = vertical_align;
+        self
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TextVertex {
+    pub position: [f32; 3],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+glium::implement_vertex!(TextVertex, position, uv, color);
+
+// text to draw in one pass, every font and depth test pair is one draw call
+#[derive(Debug, Default)]
+pub struct TextBatch<'a> {
+    groups: Vec<(&'a Font, bool, Vec<TextVertex>)>,
+}
+
+impl<'a> TextBatch<'a> {
+    // lays `text` out from `origin`, `right` and `down` are one unit of its size long
+    pub fn add(
+        &mut self,
+        font: &'a Font,
+        text: &Text,
+        origin: [f32; 3],
+        right: [f32; 3],
+        down: [f32; 3],
+    ) {
+        let index = self.groups.iter().position(|(group_font, depth_test, _)| {
+            std::ptr::eq(*group_font, font) && *depth_test == text.depth_test
+        });
+        let vertices = match index {
+            Some(index) => &mut self.groups[index].2,
+            None => {
+                self.groups.push((font, text.depth_test, Vec::new()));
+                &mut self.groups.last_mut().unwrap().2
+            }
+        };
+
+        let point = |x: f32, y: f32| {
+            [
+                origin[0] + right[0] * x + down[0] * y,
+                origin[1] + right[1] * x + down[1] * y,
+                origin[2] + right[2] * x + down[2] * y,
+            ]
+        };
+
+        for quad in font.layout(&text.text, text.size, text.align, text.vertical_align) {
+            let corner = |x: usize, y: usize| TextVertex {
+                position: point([quad.min[0], quad.max[0]][x], [quad.min[1], quad.max[1]][y]),
+                uv: [
+                    [quad.uv_min[0], quad.uv_max[0]][x],
+                    [quad.uv_min[1], quad.uv_max[1]][y],
+                ],
+                color: text.color,
+            };
+
+            vertices.extend([
+                corner(0, 0),
+                corner(0, 1),
+                corner(1, 1),
+                corner(0, 0),
+                corner(1, 1),
+                corner(1, 0),
+            ]);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups
+            .iter()
+            .all(|(_, _, vertices)| vertices.is_empty())
+    }
+
+    // `matrix` takes the text's positions to clip space. returns the number of draw calls made
+    pub fn draw<S: Surface>(
+        &self,
+        facade: &dyn Facade,
+        program: &glium::Program,
+        matrix: [[f32; 4]; 4],
+        frame: &mut S,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut draw_calls = 0;
+
+        for (font, depth_test, vertices) in &self.groups {
+            if vertices.is_empty() {
+                continue;
+            }
+
+            let buffer = glium::VertexBuffer::new(facade, vertices)?;
+            let uniforms = glium::uniform! {
+                matrix: matrix,
+                glyph_atlas: font
+                    .atlas
+                    .sampled()
+                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
+                    .minify_filter(glium::uniforms::MinifySamplerFilter::LinearMipmapLinear)
+                    .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp),
+            };
+            let params = glium::DrawParameters {
+                depth: glium::Depth {
+                    test: if *depth_test {
+                        glium::draw_parameters::DepthTest::IfLessOrEqual
+                    } else {
+                        glium::draw_parameters::DepthTest::Overwrite
+                    },
+                    write: false,
+                    ..Default::default()
+                },
+                blend: glium::Blend::alpha_blending(),
+                ..Default::default()
+            };
+
+            frame.draw(
+                &buffer,
+                glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+                program,
+                &uniforms,
+                &params,
+            )?;
+            draw_calls += 1;
+        }
+
+        Ok(draw_calls)
+    }
+}
+
+// maps pixels from the top left of a `width` by `height` target to clip space
+pub fn screen_matrix(width: u32, height: u32) -> [[f32; 4]; 4] {
+    [
+        [2.0 / width.max(1) as f32, 0.0, 0.0, 0.0],
+        [0.0, -2.0 / height.max(1) as f32, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [-1.0, 1.0, 0.0, 1.0],
+    ]
+}
+
+// every `Text` placed on the screen, drawn over the finished frame. text without a font is
+// skipped
+pub(crate) fn gather_screen_text<'a>(
+    game_state: &'a GameState,
+    default_font: Option<&'a Font>,
+) -> TextBatch<'a> {
+    let mut screen = TextBatch::default();
+
+    for text in game_state.get_components::<Text>(Text::get_component_type()) {
+        let (TextSpace::Screen(position), Some(font)) =
+            (text.space, text.font.as_deref().or(default_font))
+        else {
+            continue;
+        };
+
+        screen.add(
+            font,
+            text,
+            [position[0], position[1], 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+        );
+    }
+
+    screen
+}
+
+// every `Text` placed in the world and every debug label, facing `camera` and drawn with its
+// scene. `screen_height` is the height of the camera's viewport in pixels
+pub(crate) fn gather_world_text<'a>(
+    game_state: &'a GameState,
+    default_font: Option<&'a Font>,
+    camera: &Camera,
+    screen_height: u32,
+) -> TextBatch<'a> {
+    let mut world = TextBatch::default();
+
+    // the camera's right and up directions in world space, for billboarding
+    let view = Matrix4(camera.get_view());
+    let inverse = view.inverse().unwrap_or(Matrix4::IDENTITY);
+    let origin = inverse.transform_point([0.0; 3]);
+    let direction = |point: [f32; 3]| {
+        let point = inverse.transform_point(point);
+        normalize([
+            point[0] - origin[0],
+            point[1] - origin[1],
+            point[2] - origin[2],
+        ])
+    };
+    let right = direction([1.0, 0.0, 0.0]);
+    let up = direction([0.0, 1.0, 0.0]);
+    let down = [-up[0], -up[1], -up[2]];
+
+    for entity in game_state.get_entities_with::<Text>(Text::get_component_type()) {
+        let Some(text) = entity.get_component::<Text>(Text::get_component_type()) else {
+            continue;
+        };
+        let (TextSpace::World(offset), Some(font)) =
+            (text.space, text.font.as_deref().or(default_font))
+        else {
+            continue;
+        };
+
+        let position = entity
+            .get_component::<Transform>(Transform::get_component_type())
+            .map_or([0.0; 3], |transform| {
+                transform.get_model().transform_point([0.0; 3])
+            });
+        let position = [
+            position[0] + offset[0],
+            position[1] + offset[1],
+            position[2] + offset[2],
+        ];
+        world.add(font, text, position, right, down);
+    }
+
+    let debug = game_state
+        .get_resource::<DebugDraw>()
+        .filter(|debug| debug.enabled);
+    if let (Some(debug), Some(font)) = (debug, default_font) {
+        // world units per pixel at a distance of 1 from the camera
+        let pixel_size = 2.0 * (camera.fov.to_radians() / 2.0).tan() / screen_height.max(1) as f32;
+
+        for label in &debug.labels {
+            let distance = -view.transform_point(label.position)[2];
+            if distance <= camera.near {
+                continue;
+            }
+
+            let text = Text {
+                color: label.color,
+                depth_test: label.depth_test,
+                ..Text::world(
+                    &label.text,
+                    [0.0; 3],
+                    DEBUG_LABEL_SIZE * pixel_size * distance,
+                )
+            }
+            .with_align(TextAlign::Left, VerticalAlign::Bottom);
+            world.add(font, &text, label.position, right, down);
+        }
+    }
+
+    world
+}
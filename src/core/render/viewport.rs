@@ -0,0 +1,143 @@
+use glium::framebuffer::{MultiOutputFrameBuffer, SimpleFrameBuffer};
+use glium::index::IndicesSource;
+use glium::uniforms::{MagnifySamplerFilter, Uniforms};
+use glium::vertex::MultiVerticesSource;
+use glium::{BlitMask, BlitTarget, DrawError, DrawParameters, Program, Rect, Surface};
+
+// a part of another surface, everything drawn and cleared is moved into `rect` so a camera's
+// view can be drawn without knowing where it ends up
+pub struct ViewportSurface<'a, S: Surface> {
+    surface: &'a mut S,
+    rect: Rect,
+}
+
+impl<'a, S: Surface> ViewportSurface<'a, S> {
+    pub fn new(surface: &'a mut S, rect: Rect) -> Self {
+        ViewportSurface { surface, rect }
+    }
+
+    // from the viewport's pixels to the surface's
+    fn offset(&self, rect: &Rect) -> Rect {
+        Rect {
+            left: self.rect.left + rect.left,
+            bottom: self.rect.bottom + rect.bottom,
+            ..*rect
+        }
+    }
+
+    fn offset_blit(&self, target: &BlitTarget) -> BlitTarget {
+        BlitTarget {
+            left: self.rect.left + target.left,
+            bottom: self.rect.bottom + target.bottom,
+            ..*target
+        }
+    }
+}
+
+impl<S: Surface> Surface for ViewportSurface<'_, S> {
+    fn clear(
+        &mut self,
+        rect: Option<&Rect>,
+        color: Option<(f32, f32, f32, f32)>,
+        color_srgb: bool,
+        depth: Option<f32>,
+        stencil: Option<i32>,
+    ) {
+        let rect = rect.map_or(self.rect, |rect| self.offset(rect));
+        self.surface
+            .clear(Some(&rect), color, color_srgb, depth, stencil);
+    }
+
+    fn get_dimensions(&self) -> (u32, u32) {
+        (self.rect.width, self.rect.height)
+    }
+
+    fn get_depth_buffer_bits(&self) -> Option<u16> {
+        self.surface.get_depth_buffer_bits()
+    }
+
+    fn get_stencil_buffer_bits(&self) -> Option<u16> {
+        self.surface.get_stencil_buffer_bits()
+    }
+
+    fn draw<'a, 'b, V, I, U>(
+        &mut self,
+        vertices: V,
+        indices: I,
+        program: &Program,
+        uniforms: &U,
+        draw_parameters: &DrawParameters<'_>,
+    ) -> Result<(), DrawError>
+    where
+        V: MultiVerticesSource<'b>,
+        I: Into<IndicesSource<'a>>,
+        U: Uniforms,
+    {
+        let mut params = draw_parameters.clone();
+        params.viewport = Some(params.viewport.map_or(self.rect, |rect| self.offset(&rect)));
+        params.scissor = params.scissor.map(|rect| self.offset(&rect));
+
+        self.surface
+            .draw(vertices, indices, program, uniforms, &params)
+    }
+
+    fn blit_buffers_from_frame(
+        &self,
+        source_rect: &Rect,
+        target_rect: &BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: BlitMask,
+    ) {
+        self.surface.blit_buffers_from_frame(
+            source_rect,
+            &self.offset_blit(target_rect),
+            filter,
+            mask,
+        );
+    }
+
+    fn blit_buffers_from_simple_framebuffer(
+        &self,
+        source: &SimpleFrameBuffer<'_>,
+        source_rect: &Rect,
+        target_rect: &BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: BlitMask,
+    ) {
+        self.surface.blit_buffers_from_simple_framebuffer(
+            source,
+            source_rect,
+            &self.offset_blit(target_rect),
+            filter,
+            mask,
+        );
+    }
+
+    fn blit_buffers_from_multioutput_framebuffer(
+        &self,
+        source: &MultiOutputFrameBuffer<'_>,
+        source_rect: &Rect,
+        target_rect: &BlitTarget,
+        filter: MagnifySamplerFilter,
+        mask: BlitMask,
+    ) {
+        self.surface.blit_buffers_from_multioutput_framebuffer(
+            source,
+            source_rect,
+            &self.offset_blit(target_rect),
+            filter,
+            mask,
+        );
+    }
+
+    fn blit_color<T: Surface>(
+        &self,
+        source_rect: &Rect,
+        target: &T,
+        target_rect: &BlitTarget,
+        filter: MagnifySamplerFilter,
+    ) {
+        self.surface
+            .blit_color(&self.offset(source_rect), target, target_rect, filter);
+    }
+}
@@ -0,0 +1,314 @@
+use crate::core::*;
+use std::fmt::Write;
+
+struct Stage<'a> {
+    name: &'static str,
+    systems: &'a Vec<System>,
+    execution_order: &'a Vec<Vec<usize>>,
+}
+
+impl Scheduler {
+    fn stages(&self) -> [Stage<'_>; 4] {
+        [
+            Stage {
+                name: "Init",
+                systems: &self.init_systems,
+                execution_order: &self.init_execution_order,
+            },
+            Stage {
+                name: "Update",
+                systems: &self.update_systems,
+                execution_order: &self.update_execution_order,
+            },
+            Stage {
+                name: "FixedUpdate",
+                systems: &self.fixed_update_systems,
+                execution_order: &self.fixed_update_execution_order,
+            },
+            Stage {
+                name: "Close",
+                systems: &self.close_systems,
+                execution_order: &self.close_execution_order,
+            },
+        ]
+    }
+
+    // human readable dump of every stage's groups, the access of each system and the conflicts
+    // that kept systems out of the same group
+    pub fn schedule_report(&self) -> String {
+        let mut report = String::new();
+
+        for stage in self.stages() {
+            let _ = writeln!(report, "{}", stage.name);
+
+            for (group_index, group) in stage.execution_order.iter().enumerate() {
+                let _ = writeln!(report, "  group {}", group_index);
+                for system_index in group {
+                    let system = &stage.systems[*system_index];
+                    let _ = writeln!(
+                        report,
+                        "    {} ({})",
+                        system.name,
+                        system.arg_names.join(", ")
+                    );
+                }
+            }
+
+            let conflicts = stage_conflicts(&stage);
+            if !conflicts.is_empty() {
+                let _ = writeln!(report, "  conflicts");
+                for (a, b, shared) in conflicts {
+                    let _ = writeln!(
+                        report,
+                        "    {} <-> {}: {}",
+                        stage.systems[a].name,
+                        stage.systems[b].name,
+                        shared.join(", ")
+                    );
+                }
+            }
+
+            let splits = unforced_splits(&stage);
+            if !splits.is_empty() {
+                let _ = writeln!(report, "  splits without a conflict");
+                for (system_index, previous_group) in splits {
+                    let _ = writeln!(
+                        report,
+                        "    {} shares no access with group {}",
+                        stage.systems[system_index].name, previous_group
+                    );
+                }
+            }
+        }
+
+        report
+    }
+
+    // same information as `schedule_report` as a graphviz graph, render it with
+    // `dot -Tsvg schedule.dot -o schedule.svg`
+    pub fn schedule_dot(&self) -> String {
+        let mut dot = String::new();
+
+        let _ = writeln!(dot, "digraph schedule {{");
+        let _ = writeln!(dot, "    rankdir=LR;");
+        let _ = writeln!(dot, "    node [shape=box];");
+
+        for stage in self.stages() {
+            let _ = writeln!(dot, "    subgraph cluster_{} {{", stage.name);
+            let _ = writeln!(dot, "        label=\"{}\";", stage.name);
+
+            for (group_index, group) in stage.execution_order.iter().enumerate() {
+                let _ = writeln!(
+                    dot,
+                    "        subgraph cluster_{}_{} {{",
+                    stage.name, group_index
+                );
+                let _ = writeln!(dot, "            label=\"group {}\";", group_index);
+                for system_index in group {
+                    let system = &stage.systems[*system_index];
+                    let _ = writeln!(
+                        dot,
+                        "            {}_{} [label=\"{}\\n({})\"];",
+                        stage.name,
+                        system_index,
+                        system.name,
+                        system.arg_names.join(", ")
+                    );
+                }
+                let _ = writeln!(dot, "        }}");
+            }
+
+            // groups run one after another
+            for pair in stage.execution_order.windows(2) {
+                let _ = writeln!(
+                    dot,
+                    "        {}_{} -> {}_{} [style=bold];",
+                    stage.name, pair[0][0], stage.name, pair[1][0]
+                );
+            }
+
+            for (a, b, shared) in stage_conflicts(&stage) {
+                let _ = writeln!(
+                    dot,
+                    "        {}_{} -> {}_{} [dir=none, style=dashed, color=red, label=\"{}\"];",
+                    stage.name,
+                    a,
+                    stage.name,
+                    b,
+                    shared.join(", ")
+                );
+            }
+
+            for (system_index, previous_group) in unforced_splits(&stage) {
+                let _ = writeln!(
+                    dot,
+                    "        {}_{} -> {}_{} [style=dotted, label=\"no conflict\"];",
+                    stage.name, stage.execution_order[previous_group][0], stage.name, system_index
+                );
+            }
+
+            let _ = writeln!(dot, "    }}");
+        }
+
+        let _ = writeln!(dot, "}}");
+
+        dot
+    }
+
+    // writes graphviz output for `.dot` paths and the text report otherwise
+    pub fn write_schedule(&self, path: &str) -> std::io::Result<()> {
+        let contents = if path.ends_with(".dot") {
+            self.schedule_dot()
+        } else {
+            self.schedule_report()
+        };
+
+        std::fs::write(path, contents)
+    }
+}
+
+// pairs of systems in different groups of a stage along with the accesses they share
+fn stage_conflicts(stage: &Stage) -> Vec<(usize, usize, Vec<&'static str>)> {
+    let mut group_of = vec![0; stage.systems.len()];
+    for (group_index, group) in stage.execution_order.iter().enumerate() {
+        for system_index in group {
+            group_of[*system_index] = group_index;
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    for a in 0..stage.systems.len() {
+        for b in a + 1..stage.systems.len() {
+            if group_of[a] == group_of[b] {
+                continue;
+            }
+
+            let shared = shared_access(&stage.systems[a], &stage.systems[b]);
+            if !shared.is_empty() {
+                conflicts.push((a, b, shared));
+            }
+        }
+    }
+
+    conflicts
+}
+
+// systems that share no access with anything in the group before theirs, so nothing but the order
+// of the grouping kept them out of it. pairs of the system and that group
+fn unforced_splits(stage: &Stage) -> Vec<(usize, usize)> {
+    let mut splits = Vec::new();
+
+    for (group_index, pair) in stage.execution_order.windows(2).enumerate() {
+        for system_index in &pair[1] {
+            let system = &stage.systems[*system_index];
+            let conflicts = pair[0]
+                .iter()
+                .any(|other| !shared_access(system, &stage.systems[*other]).is_empty());
+
+            if !conflicts {
+                splits.push((*system_index, group_index));
+            }
+        }
+    }
+
+    splits
+}
+
+fn shared_access(a: &System, b: &System) -> Vec<&'static str> {
+    let exclusive = GameState::get_component_type();
+    if a.args.contains(&exclusive) || b.args.contains(&exclusive) {
+        return vec!["GameState"];
+    }
+
+    a.args
+        .iter()
+        .zip(a.arg_names.iter())
+        .filter(|(component, _)| b.args.contains(component))
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn physics(_game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+        Ok(())
+    }
+    create_system!(physics, get_physics_system;
+        uses Time);
+
+    async fn animation(_game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+        Ok(())
+    }
+    create_system!(animation, get_animation_system;
+        uses Time);
+
+    async fn timers(_game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+        Ok(())
+    }
+    create_system!(timers, get_timers_system;
+        uses Timers);
+
+    // physics and timers share nothing and run together, animation waits for physics
+    fn scheduler() -> Scheduler {
+        let mut scheduler = Scheduler::new(0.01);
+        scheduler.add_system(get_physics_system(), SystemType::Update);
+        scheduler.add_system(get_animation_system(), SystemType::Update);
+        scheduler.add_system(get_timers_system(), SystemType::Update);
+        scheduler
+    }
+
+    #[test]
+    fn report_lists_groups_and_conflicts() {
+        let expected: String = [
+            "Init",
+            "Update",
+            "  group 0",
+            "    physics (Time)",
+            "    timers (Timers)",
+            "  group 1",
+            "    animation (Time)",
+            "  conflicts",
+            "    physics <-> animation: Time",
+            "FixedUpdate",
+            "Close",
+        ]
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect();
+
+        assert_eq!(scheduler().schedule_report(), expected);
+    }
+
+    #[test]
+    fn report_labels_splits_without_a_conflict() {
+        let mut scheduler = scheduler();
+        scheduler.update_execution_order = vec![vec![0], vec![1, 2]];
+
+        let report = scheduler.schedule_report();
+        assert!(report
+            .contains("  splits without a conflict\n    timers shares no access with group 0\n"));
+        assert!(!report.contains("animation shares"));
+    }
+
+    #[test]
+    fn dot_has_a_cluster_per_group_and_an_edge_per_conflict() {
+        let mut scheduler = scheduler();
+        let dot = scheduler.schedule_dot();
+
+        assert!(dot.starts_with("digraph schedule {\n"));
+        assert!(dot.contains("subgraph cluster_Update_0 {"));
+        assert!(dot.contains("subgraph cluster_Update_1 {"));
+        assert!(dot.contains("Update_0 [label=\"physics\\n(Time)\"];"));
+        assert!(dot.contains("Update_0 -> Update_1 [style=bold];"));
+        assert!(dot
+            .contains("Update_0 -> Update_1 [dir=none, style=dashed, color=red, label=\"Time\"];"));
+        assert!(!dot.contains("no conflict"));
+
+        scheduler.update_execution_order = vec![vec![0], vec![1, 2]];
+        assert!(scheduler
+            .schedule_dot()
+            .contains("Update_0 -> Update_2 [style=dotted, label=\"no conflict\"];"));
+    }
+}
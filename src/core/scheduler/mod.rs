@@ -1,10 +1,23 @@
 use crate::core::*;
-use futures::future::poll_fn;
+use futures::future::join_all;
+use futures::FutureExt;
 use std::cell::SyncUnsafeCell;
+use std::panic::AssertUnwindSafe;
 use tokio::sync::Mutex;
 use tokio::time::Duration;
 use tokio::time::Instant;
 
+pub mod time;
+pub use time::*;
+
+pub mod timer;
+pub use timer::*;
+
+pub mod tasks;
+pub use tasks::*;
+
+mod graph;
+
 pub struct Scheduler {
     init_systems: Vec<System>,
     update_systems: Vec<System>,
@@ -18,6 +31,9 @@ pub struct Scheduler {
 
     execution_lock: SchedulerLock,
 
+    // used for systems that don't set their own policy
+    pub error_policy: ErrorPolicy,
+
     fixed_update_interval: Duration,
     start_time: Instant,
     prev_time: SyncUnsafeCell<f64>,
@@ -56,7 +72,7 @@ impl Scheduler {
             let dur = Instant::now().duration_since(time);
 
             if dur < self.fixed_update_interval {
-                tokio::time::sleep(dur).await;
+                tokio::time::sleep(self.fixed_update_interval - dur).await;
             } else {
                 eprintln!(
                     "Fixed update overran by {:?}",
@@ -82,6 +98,8 @@ impl Scheduler {
 
             execution_lock: SchedulerLock(Mutex::new(false)),
 
+            error_policy: ErrorPolicy::Log,
+
             fixed_update_interval: Duration::from_secs_f64(fixed_update_interval),
             start_time: Instant::now(),
             prev_time: SyncUnsafeCell::new(0.0),
@@ -128,57 +146,96 @@ impl Scheduler {
     }
 
     pub async fn init(&mut self, game_state: &mut GameState) {
-        let time = self.get_time();
+        self.execution_lock.lock().await;
+
+        let (time, _) = self.advance_time(game_state);
         let dt = 0.0;
-        unsafe {
-            self.prev_time.get().write(time);
-        }
 
-        self.execution_lock.lock().await;
         for group in self.init_execution_order.iter() {
-            Self::await_group(group, &self.init_systems, game_state, time, dt).await;
+            Self::await_group(
+                group,
+                &self.init_systems,
+                game_state,
+                time,
+                dt,
+                self.error_policy,
+            )
+            .await;
         }
         self.execution_lock.unlock().await;
     }
 
     pub async fn update(&self, game_state: &mut GameState) {
-        let time = self.get_time();
-        // this is ok because update and init are never run at the same time
-        let dt = time - unsafe { *self.prev_time.get() };
-        unsafe {
-            self.prev_time.get().write(time);
-        }
-
         // used to ensure that update and fixed_update don't run at the same time
         self.execution_lock.lock().await;
+
+        let (time, dt) = self.advance_time(game_state);
+
         for group in self.update_execution_order.iter() {
-            Self::await_group(group, &self.update_systems, game_state, time, dt).await;
+            Self::await_group(
+                group,
+                &self.update_systems,
+                game_state,
+                time,
+                dt,
+                self.error_policy,
+            )
+            .await;
         }
         self.execution_lock.unlock().await;
     }
 
     pub async fn fixed_update(&self, game_state: *mut GameState) {
-        let time = self.get_time();
-        let dt = self.fixed_update_interval.as_secs_f64();
+        let interval = self.fixed_update_interval.as_secs_f64();
 
         // used to ensure that update and fixed_update don't run at the same time
         self.execution_lock.lock().await;
-        for group in self.fixed_update_execution_order.iter() {
-            Self::await_group(group, &self.fixed_update_systems, game_state, time, dt).await;
+
+        // without a `Time` resource there is nothing to accumulate against, so run a single
+        // step on the real clock. the steps are capped since systems that take longer than the
+        // interval would otherwise never catch up
+        for step in 0..MAX_FIXED_STEPS {
+            let (time, dt) = match unsafe { &mut *game_state }.get_resource_mut::<Time>() {
+                Some(clock) => {
+                    if !clock.expend_fixed(interval) {
+                        break;
+                    }
+                    (clock.fixed.elapsed, clock.fixed.delta)
+                }
+                None if step == 0 => (self.get_time(), interval),
+                None => break,
+            };
+
+            for group in self.fixed_update_execution_order.iter() {
+                Self::await_group(
+                    group,
+                    &self.fixed_update_systems,
+                    game_state,
+                    time,
+                    dt,
+                    self.error_policy,
+                )
+                .await;
+            }
         }
         self.execution_lock.unlock().await;
     }
 
     pub async fn close(&self, game_state: &mut GameState) {
-        let time = self.get_time();
-        let dt = time - unsafe { *self.prev_time.get() };
-        unsafe {
-            self.prev_time.get().write(time);
-        }
-
         self.execution_lock.lock().await;
+
+        let (time, dt) = self.advance_time(game_state);
+
         for group in self.close_execution_order.iter() {
-            Self::await_group(group, &self.close_systems, game_state, time, dt).await;
+            Self::await_group(
+                group,
+                &self.close_systems,
+                game_state,
+                time,
+                dt,
+                self.error_policy,
+            )
+            .await;
         }
         self.execution_lock.unlock().await;
     }
@@ -197,25 +254,66 @@ impl Scheduler {
         game_state: *mut GameState,
         time: f64,
         dt: f64,
+        default_policy: ErrorPolicy,
     ) {
-        let mut futures = Vec::with_capacity(group.len());
-
-        // Run all systems in the group
-        for system_index in group.iter() {
-            let system = &systems[*system_index];
-            futures.push((system.system)(game_state, time, dt));
-        }
+        // Run all enabled systems in the group, catching panics so one broken system can't take
+        // the whole frame down with it
+        let futures = group
+            .iter()
+            .filter(|system_index| systems[**system_index].is_enabled())
+            .map(|system_index| {
+                let system = &systems[*system_index];
+                async move {
+                    // timed from the first poll, so systems earlier in the group aren't counted
+                    let start = Instant::now();
+                    let result = AssertUnwindSafe((system.system)(game_state, time, dt))
+                        .catch_unwind()
+                        .await;
+                    system.set_last_duration(start.elapsed());
+                    (system, result)
+                }
+            });
 
         // Wait for all futures to complete
-        poll_fn(|cx| {
-            for future in futures.iter_mut() {
-                if let std::task::Poll::Pending = future.as_mut().poll(cx) {
-                    return std::task::Poll::Pending;
+        let results = join_all(futures).await;
+
+        for (system, result) in results {
+            let error = match result {
+                Ok(Ok(())) => continue,
+                Ok(Err(e)) => e.to_string(),
+                Err(panic) => {
+                    if let Some(message) = panic.downcast_ref::<&str>() {
+                        format!("panicked: {}", message)
+                    } else if let Some(message) = panic.downcast_ref::<String>() {
+                        format!("panicked: {}", message)
+                    } else {
+                        "panicked".to_string()
+                    }
+                }
+            };
+
+            match system.error_policy.unwrap_or(default_policy) {
+                ErrorPolicy::Log => eprintln!("System {} failed: {}", system.name, error),
+                ErrorPolicy::Disable => {
+                    eprintln!("System {} failed, disabling it: {}", system.name, error);
+                    system.set_enabled(false);
+                }
+                ErrorPolicy::Close => {
+                    eprintln!("System {} failed, closing: {}", system.name, error);
+                    unsafe { &mut *game_state }
+                        .close_with_error(format!("System {} failed: {}", system.name, error));
                 }
             }
-            std::task::Poll::Ready(())
-        })
-        .await;
+        }
+    }
+
+    pub fn systems(&self, system_type: SystemType) -> &[System] {
+        match system_type {
+            SystemType::Init => &self.init_systems,
+            SystemType::Update => &self.update_systems,
+            SystemType::FixedUpdate => &self.fixed_update_systems,
+            SystemType::Close => &self.close_systems,
+        }
     }
 
     pub fn generate_execution_order(&mut self) {
@@ -233,6 +331,7 @@ impl Scheduler {
 
         for i in 0..systems.len() {
             if !visited[i] {
+                visited[i] = true;
                 let mut group = vec![i];
                 let mut dissallowed_components = systems[i].args.clone();
 
@@ -272,4 +371,137 @@ impl Scheduler {
     pub fn get_time(&self) -> f64 {
         self.start_time.elapsed().as_secs_f64()
     }
+
+    // returns the (t, dt) passed to systems, taken from the virtual clock of the `Time` resource
+    // when there is one and from the real clock otherwise
+    fn advance_time(&self, game_state: &mut GameState) -> (f64, f64) {
+        if let Some(clock) = game_state.get_resource_mut::<Time>() {
+            clock.update();
+            return (clock.virtual_clock.elapsed, clock.virtual_clock.delta);
+        }
+
+        let time = self.get_time();
+        // this is ok because it is only called while holding the execution lock
+        let dt = time - unsafe { *self.prev_time.get() };
+        unsafe {
+            self.prev_time.get().write(time);
+        }
+
+        (time, dt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use std::sync::Arc;
+
+    async fn no_access(_game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+        Ok(())
+    }
+    create_system!(no_access, get_no_access_system);
+
+    async fn uses_time(_game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+        Ok(())
+    }
+    create_system!(uses_time, get_uses_time_system;
+        uses Time);
+
+    #[test]
+    fn systems_are_scheduled_once() {
+        let scheduler = Scheduler::new(0.01);
+        let systems = vec![
+            get_no_access_system(),
+            get_uses_time_system(),
+            get_uses_time_system(),
+        ];
+
+        // a system without declared access used to be added to its own group a second time
+        assert_eq!(
+            scheduler.generate_execution_order_for_systems(&systems),
+            vec![vec![0, 1], vec![2]]
+        );
+    }
+
+    async fn failing(_game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+        Err("broken".into())
+    }
+    create_system!(failing, get_failing_system);
+
+    async fn panicking(_game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+        panic!("broken")
+    }
+    create_system!(panicking, get_panicking_system);
+
+    // runs the systems as a single group
+    fn run_group(systems: Vec<System>, default_policy: ErrorPolicy) -> (Vec<System>, GameState) {
+        let mut game_state = GameState::new(std::ptr::null_mut(), Arc::new(Config::default()));
+        let group: Vec<usize> = (0..systems.len()).collect();
+
+        futures::executor::block_on(Scheduler::await_group(
+            &group,
+            &systems,
+            &mut game_state,
+            0.0,
+            0.0,
+            default_policy,
+        ));
+
+        (systems, game_state)
+    }
+
+    #[test]
+    fn log_policy_keeps_the_system_running() {
+        let (systems, game_state) = run_group(
+            vec![get_failing_system(), get_panicking_system()],
+            ErrorPolicy::Log,
+        );
+
+        assert!(systems.iter().all(System::is_enabled));
+        assert!(!game_state.should_close);
+    }
+
+    #[test]
+    fn disable_policy_disables_the_system() {
+        let (systems, game_state) = run_group(
+            vec![
+                get_failing_system().with_error_policy(ErrorPolicy::Disable),
+                get_panicking_system().with_error_policy(ErrorPolicy::Disable),
+            ],
+            ErrorPolicy::Log,
+        );
+
+        assert!(!systems.iter().any(System::is_enabled));
+        assert!(!game_state.should_close);
+    }
+
+    #[test]
+    fn close_policy_closes_with_the_error() {
+        let (_, game_state) = run_group(
+            vec![get_failing_system().with_error_policy(ErrorPolicy::Close)],
+            ErrorPolicy::Log,
+        );
+
+        assert!(game_state.should_close);
+        assert_eq!(
+            game_state.close_error.as_deref(),
+            Some("System failing failed: broken")
+        );
+    }
+
+    #[test]
+    fn systems_fall_back_to_the_default_policy() {
+        let (systems, game_state) = run_group(
+            vec![
+                get_panicking_system(),
+                get_failing_system().with_error_policy(ErrorPolicy::Log),
+            ],
+            ErrorPolicy::Disable,
+        );
+
+        assert!(!systems[0].is_enabled());
+        assert!(systems[1].is_enabled());
+        assert!(!game_state.should_close);
+    }
 }
@@ -0,0 +1,149 @@
+use crate::core::*;
+use futures::FutureExt;
+use std::future::Future;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::{JoinError, JoinHandle};
+
+type Completion = Box<dyn FnOnce(&mut GameState) + Send>;
+
+// component type 11
+pub struct TaskPool {
+    runtime: Handle,
+    sender: UnboundedSender<Completion>,
+    receiver: UnboundedReceiver<Completion>,
+}
+impl_resource!(TaskPool, 11);
+
+impl TaskPool {
+    pub fn new(runtime: Handle) -> TaskPool {
+        let (sender, receiver) = unbounded_channel();
+
+        TaskPool {
+            runtime,
+            sender,
+            receiver,
+        }
+    }
+
+    // the result is retrieved by polling the returned `Task`
+    pub fn spawn<T: Send + 'static>(
+        &self,
+        future: impl Future<Output = T> + Send + 'static,
+    ) -> Task<T> {
+        Task {
+            handle: Some(self.runtime.spawn(future)),
+        }
+    }
+
+    // for cpu bound work like pathfinding that would otherwise stall a worker thread
+    pub fn spawn_blocking<T: Send + 'static>(
+        &self,
+        work: impl FnOnce() -> T + Send + 'static,
+    ) -> Task<T> {
+        Task {
+            handle: Some(self.runtime.spawn_blocking(work)),
+        }
+    }
+
+    // `apply` runs with the result on the next `apply_task_results` sync point, where it has
+    // exclusive access to the game state
+    pub fn spawn_then<T: Send + 'static>(
+        &self,
+        future: impl Future<Output = T> + Send + 'static,
+        apply: impl FnOnce(&mut GameState, T) + Send + 'static,
+    ) -> Task<()> {
+        let sender = self.sender.clone();
+
+        self.spawn(async move {
+            let result = future.await;
+            // only fails if the pool was dropped, in which case there is nowhere to apply it
+            let _ = sender.send(Box::new(move |game_state| apply(game_state, result)));
+        })
+    }
+
+    fn take_completed(&mut self) -> Vec<Completion> {
+        let mut completed = Vec::new();
+        while let Ok(completion) = self.receiver.try_recv() {
+            completed.push(completion);
+        }
+        completed
+    }
+}
+
+pub struct Task<T> {
+    // taken once the result has been returned so it is never polled after completion
+    handle: Option<JoinHandle<T>>,
+}
+
+impl<T> Task<T> {
+    pub fn is_finished(&self) -> bool {
+        match &self.handle {
+            Some(handle) => handle.is_finished(),
+            None => true,
+        }
+    }
+
+    // returns the result once, after the task is done, `None` while it is still running
+    pub fn poll(&mut self) -> Option<Result<T, JoinError>> {
+        if !self.handle.as_ref()?.is_finished() {
+            return None;
+        }
+
+        // polled in place, the coop budget can make a finished handle return pending and the
+        // result would be lost if the handle were dropped
+        let result = self.handle.as_mut()?.now_or_never()?;
+        self.handle = None;
+        Some(result)
+    }
+
+    pub fn cancel(&self) {
+        if let Some(handle) = &self.handle {
+            handle.abort();
+        }
+    }
+}
+
+//uses GameState because completions can touch anything
+create_system!(apply_task_results, get_apply_task_results_system;
+    uses GameState, TaskPool);
+async fn apply_task_results(game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+    let completed = match game_state.get_resource_mut::<TaskPool>() {
+        Some(task_pool) => task_pool.take_completed(),
+        None => return Ok(()),
+    };
+
+    for completion in completed {
+        completion(game_state);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poll_returns_the_result_once() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .build()
+            .unwrap();
+        let pool = TaskPool::new(runtime.handle().clone());
+
+        let mut task = pool.spawn(async { 42 });
+        while !task.is_finished() {
+            std::thread::yield_now();
+        }
+
+        let mut results = Vec::new();
+        for _ in 0..100 {
+            if let Some(result) = task.poll() {
+                results.push(result.unwrap());
+            }
+        }
+        assert_eq!(results, vec![42]);
+        assert!(task.is_finished());
+    }
+}
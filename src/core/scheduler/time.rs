@@ -0,0 +1,259 @@
+use crate::core::*;
+use tokio::time::Instant;
+
+// most fixed steps run per frame, time beyond that is dropped so a slow frame can't make the next
+// one slower by running even more steps
+pub const MAX_FIXED_STEPS: u32 = 5;
+
+#[derive(Debug)]
+pub enum ClockSource {
+    Real(Instant),
+    // only moves when `Time::advance` is called, used for deterministic stepping
+    Manual(f64),
+}
+
+impl ClockSource {
+    pub fn now(&self) -> f64 {
+        match self {
+            ClockSource::Real(start) => start.elapsed().as_secs_f64(),
+            ClockSource::Manual(now) => *now,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Clock {
+    pub elapsed: f64,
+    pub delta: f64,
+}
+
+impl Clock {
+    pub fn tick(&mut self, delta: f64) {
+        self.delta = delta;
+        self.elapsed += delta;
+    }
+}
+
+// component type 8
+#[derive(Debug)]
+pub struct Time {
+    pub source: ClockSource,
+
+    // wall clock time, unaffected by `time_scale` and `paused`
+    pub real: Clock,
+    // the clock `Update` and `Close` systems see
+    pub virtual_clock: Clock,
+    // the clock `FixedUpdate` systems see, only advances in whole fixed steps
+    pub fixed: Clock,
+
+    pub time_scale: f64,
+    pub paused: bool,
+
+    last_update: f64,
+    last_fixed: f64,
+    fixed_accumulator: f64,
+}
+impl_resource!(Time, 8, debug);
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Time {
+    pub fn new() -> Time {
+        Self::with_source(ClockSource::Real(Instant::now()))
+    }
+
+    pub fn new_manual() -> Time {
+        Self::with_source(ClockSource::Manual(0.0))
+    }
+
+    pub fn with_source(source: ClockSource) -> Time {
+        let now = source.now();
+
+        Time {
+            source,
+            real: Clock::default(),
+            virtual_clock: Clock::default(),
+            fixed: Clock::default(),
+            time_scale: 1.0,
+            paused: false,
+            last_update: now,
+            last_fixed: now,
+            fixed_accumulator: 0.0,
+        }
+    }
+
+    // moves a manual clock forward, does nothing for a real clock
+    pub fn advance(&mut self, seconds: f64) {
+        if let ClockSource::Manual(now) = &mut self.source {
+            *now += seconds;
+        }
+    }
+
+    // swaps in a manual clock that continues from the current time
+    pub fn use_manual_clock(&mut self) {
+        self.source = ClockSource::Manual(self.source.now());
+    }
+
+    // swaps back to the wall clock, continuing from the current time
+    pub fn use_real_clock(&mut self) {
+        let now = self.source.now();
+        match Instant::now().checked_sub(std::time::Duration::from_secs_f64(now)) {
+            Some(start) => self.source = ClockSource::Real(start),
+            // further back than the system clock goes, so restart from zero and move the last
+            // update times along with it
+            None => {
+                self.source = ClockSource::Real(Instant::now());
+                self.last_update -= now;
+                self.last_fixed -= now;
+            }
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    fn scale(&self, delta: f64) -> f64 {
+        if self.paused {
+            0.0
+        } else {
+            delta * self.time_scale
+        }
+    }
+
+    // called by the scheduler once per frame before running systems
+    pub fn update(&mut self) {
+        let now = self.source.now();
+        let delta = now - self.last_update;
+        self.last_update = now;
+
+        self.real.tick(delta);
+        let scaled = self.scale(delta);
+        self.virtual_clock.tick(scaled);
+    }
+
+    // accumulates scaled time and consumes one fixed step if enough has built up,
+    // returns whether `FixedUpdate` systems should run
+    pub fn expend_fixed(&mut self, interval: f64) -> bool {
+        let now = self.source.now();
+        let delta = now - self.last_fixed;
+        self.last_fixed = now;
+
+        self.fixed_accumulator =
+            (self.fixed_accumulator + self.scale(delta)).min(interval * MAX_FIXED_STEPS as f64);
+
+        if self.fixed_accumulator < interval {
+            return false;
+        }
+
+        self.fixed_accumulator -= interval;
+        self.fixed.tick(interval);
+        true
+    }
+}
+
+// the wall clock delta for things that should keep going while the game is paused or slowed down,
+// `dt` when there is no `Time` resource
+pub fn real_delta_or(game_state: &GameState, dt: f64) -> f64 {
+    game_state
+        .get_resource::<Time>()
+        .map_or(dt, |time| time.real.delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed_steps(time: &mut Time, interval: f64) -> u32 {
+        let mut steps = 0;
+        while time.expend_fixed(interval) {
+            steps += 1;
+        }
+        steps
+    }
+
+    #[test]
+    fn virtual_clock_is_scaled() {
+        let mut time = Time::new_manual();
+        time.set_time_scale(2.0);
+        time.advance(0.5);
+        time.update();
+
+        assert_eq!(time.real.delta, 0.5);
+        assert_eq!(time.virtual_clock.delta, 1.0);
+        assert_eq!(time.virtual_clock.elapsed, 1.0);
+    }
+
+    #[test]
+    fn paused_virtual_clock_stands_still() {
+        let mut time = Time::new_manual();
+        time.pause();
+        time.advance(0.5);
+        time.update();
+
+        assert_eq!(time.real.delta, 0.5);
+        assert_eq!(time.virtual_clock.delta, 0.0);
+        assert_eq!(fixed_steps(&mut time, 0.25), 0);
+
+        time.resume();
+        time.advance(0.5);
+        time.update();
+        assert_eq!(time.virtual_clock.elapsed, 0.5);
+        assert_eq!(fixed_steps(&mut time, 0.25), 2);
+    }
+
+    #[test]
+    fn fixed_clock_runs_whole_steps() {
+        let mut time = Time::new_manual();
+        time.advance(0.6);
+        assert_eq!(fixed_steps(&mut time, 0.25), 2);
+        assert_eq!(time.fixed.elapsed, 0.5);
+        assert_eq!(time.fixed.delta, 0.25);
+
+        // the left over 0.1 carries into the next frame
+        time.advance(0.15);
+        assert_eq!(fixed_steps(&mut time, 0.25), 1);
+    }
+
+    #[test]
+    fn fixed_clock_is_scaled() {
+        let mut time = Time::new_manual();
+        time.set_time_scale(0.5);
+        time.advance(1.0);
+        assert_eq!(fixed_steps(&mut time, 0.25), 2);
+    }
+
+    #[test]
+    fn real_clock_continues_from_manual_clock() {
+        let mut time = Time::new_manual();
+        time.advance(1e12);
+        time.update();
+
+        time.use_real_clock();
+        time.update();
+        assert!(time.real.delta >= 0.0 && time.real.delta < 1.0);
+    }
+
+    #[test]
+    fn fixed_steps_are_capped() {
+        let mut time = Time::new_manual();
+        time.advance(100.0);
+        assert_eq!(fixed_steps(&mut time, 0.25), MAX_FIXED_STEPS);
+
+        // the dropped time isn't made up later
+        time.advance(0.0);
+        assert_eq!(fixed_steps(&mut time, 0.25), 0);
+    }
+}
@@ -0,0 +1,338 @@
+use crate::core::*;
+use std::any::Any;
+
+pub type TimerId = u64;
+
+struct ScheduledCallback {
+    id: TimerId,
+    due: f64,
+    // `None` for one-shot callbacks
+    interval: Option<f64>,
+    callback: Box<dyn FnMut(&mut GameState)>,
+}
+
+// component type 9
+pub struct Timers {
+    // virtual time of the last `tick_timers` run, delays are measured from here
+    now: f64,
+    next_id: TimerId,
+    scheduled: Vec<ScheduledCallback>,
+    // ids cancelled while their callback was running
+    cancelled: Vec<TimerId>,
+}
+impl_resource!(Timers, 9);
+
+impl Default for Timers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timers {
+    pub fn new() -> Timers {
+        Timers {
+            now: 0.0,
+            next_id: 0,
+            scheduled: Vec::new(),
+            cancelled: Vec::new(),
+        }
+    }
+
+    // runs `callback` once after `delay` seconds of virtual time
+    pub fn after(&mut self, delay: f64, callback: impl FnMut(&mut GameState) + 'static) -> TimerId {
+        self.schedule(delay, None, Box::new(callback))
+    }
+
+    // runs `callback` every `interval` seconds of virtual time, starting one interval from now
+    pub fn every(
+        &mut self,
+        interval: f64,
+        callback: impl FnMut(&mut GameState) + 'static,
+    ) -> TimerId {
+        self.schedule(interval, Some(interval), Box::new(callback))
+    }
+
+    fn schedule(
+        &mut self,
+        delay: f64,
+        interval: Option<f64>,
+        callback: Box<dyn FnMut(&mut GameState)>,
+    ) -> TimerId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.scheduled.push(ScheduledCallback {
+            id,
+            due: self.now + delay,
+            interval,
+            callback,
+        });
+
+        id
+    }
+
+    pub fn cancel(&mut self, id: TimerId) {
+        let len = self.scheduled.len();
+        self.scheduled.retain(|scheduled| scheduled.id != id);
+
+        // if it wasn't scheduled it is either finished or currently running
+        if self.scheduled.len() == len {
+            self.cancelled.push(id);
+        }
+    }
+
+    pub fn is_scheduled(&self, id: TimerId) -> bool {
+        self.scheduled.iter().any(|scheduled| scheduled.id == id)
+    }
+
+    fn take_due(&mut self, now: f64) -> Vec<ScheduledCallback> {
+        self.now = now;
+
+        let (due, pending) = std::mem::take(&mut self.scheduled)
+            .into_iter()
+            .partition(|scheduled| scheduled.due <= now);
+        self.scheduled = pending;
+
+        due
+    }
+
+    fn reschedule(&mut self, mut due: Vec<ScheduledCallback>) {
+        for mut scheduled in due.drain(..) {
+            if let Some(position) = self.cancelled.iter().position(|id| *id == scheduled.id) {
+                self.cancelled.swap_remove(position);
+                continue;
+            }
+
+            if let Some(interval) = scheduled.interval {
+                // skips missed ticks instead of running them all at once after a hitch
+                while scheduled.due <= self.now {
+                    scheduled.due += interval;
+                }
+                self.scheduled.push(scheduled);
+            }
+        }
+
+        self.cancelled.clear();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimerMode {
+    Once,
+    Repeating,
+}
+
+// component type 10
+#[derive(Debug)]
+pub struct Timer {
+    pub duration: f64,
+    pub elapsed: f64,
+    pub mode: TimerMode,
+    pub paused: bool,
+
+    finished: bool,
+    // how many times the timer completed during the last tick
+    times_finished: u32,
+    cancelled: bool,
+}
+impl_component!(Timer, 10);
+
+impl Timer {
+    pub fn new(duration: f64, mode: TimerMode) -> Timer {
+        Timer {
+            duration,
+            elapsed: 0.0,
+            mode,
+            paused: false,
+            finished: false,
+            times_finished: 0,
+            cancelled: false,
+        }
+    }
+
+    pub fn once(duration: f64) -> Timer {
+        Self::new(duration, TimerMode::Once)
+    }
+
+    pub fn repeating(duration: f64) -> Timer {
+        Self::new(duration, TimerMode::Repeating)
+    }
+
+    pub fn tick(&mut self, dt: f64) {
+        self.times_finished = 0;
+
+        if self.paused || self.cancelled {
+            return;
+        }
+
+        if self.finished && self.mode == TimerMode::Once {
+            return;
+        }
+
+        self.elapsed += dt;
+
+        if self.elapsed < self.duration {
+            return;
+        }
+
+        self.finished = true;
+
+        match self.mode {
+            TimerMode::Once => {
+                self.elapsed = self.duration;
+                self.times_finished = 1;
+            }
+            TimerMode::Repeating if self.duration > 0.0 => {
+                self.times_finished = (self.elapsed / self.duration) as u32;
+                self.elapsed %= self.duration;
+            }
+            TimerMode::Repeating => {
+                self.times_finished = 1;
+                self.elapsed = 0.0;
+            }
+        }
+    }
+
+    // true only for the tick in which the timer completed
+    pub fn just_finished(&self) -> bool {
+        self.times_finished > 0
+    }
+
+    pub fn times_finished(&self) -> u32 {
+        self.times_finished
+    }
+
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn remaining(&self) -> f64 {
+        (self.duration - self.elapsed).max(0.0)
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.finished = false;
+        self.times_finished = 0;
+        self.cancelled = false;
+    }
+
+    // stops the timer for good, unlike `paused` it is only undone by `reset`
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+        self.times_finished = 0;
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+//uses GameState because scheduled callbacks can touch anything
+create_system!(tick_timers, get_tick_timers_system;
+    uses GameState, Timers, Timer);
+async fn tick_timers(game_state: &mut GameState, t: f64, dt: f64) -> SystemResult {
+    for timer in game_state.get_components_mut::<Timer>(Timer::get_component_type()) {
+        timer.tick(dt);
+    }
+
+    let mut due = match game_state.get_resource_mut::<Timers>() {
+        Some(timers) => timers.take_due(t),
+        None => return Ok(()),
+    };
+
+    due.sort_by(|a, b| a.due.total_cmp(&b.due));
+    for scheduled in due.iter_mut() {
+        // an earlier callback in this batch may have cancelled this one
+        let cancelled = game_state
+            .get_resource::<Timers>()
+            .is_some_and(|timers| timers.cancelled.contains(&scheduled.id));
+        if cancelled {
+            continue;
+        }
+
+        (scheduled.callback)(game_state);
+    }
+
+    if let Some(timers) = game_state.get_resource_mut::<Timers>() {
+        timers.reschedule(due);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn once_timer_finishes_once() {
+        let mut timer = Timer::once(1.0);
+        timer.tick(0.5);
+        assert!(!timer.finished());
+        assert_eq!(timer.remaining(), 0.5);
+
+        timer.tick(0.75);
+        assert!(timer.just_finished());
+        assert_eq!(timer.elapsed, 1.0);
+
+        timer.tick(1.0);
+        assert!(timer.finished());
+        assert!(!timer.just_finished());
+    }
+
+    #[test]
+    fn repeating_timer_counts_every_completion() {
+        let mut timer = Timer::repeating(0.25);
+        timer.tick(0.625);
+        assert_eq!(timer.times_finished(), 2);
+        assert_eq!(timer.elapsed, 0.125);
+
+        timer.tick(0.0);
+        assert!(!timer.just_finished());
+    }
+
+    #[test]
+    fn paused_and_cancelled_timers_stand_still() {
+        let mut timer = Timer::once(1.0);
+        timer.paused = true;
+        timer.tick(2.0);
+        assert_eq!(timer.elapsed, 0.0);
+
+        timer.paused = false;
+        timer.cancel();
+        timer.tick(2.0);
+        assert!(!timer.finished());
+
+        timer.reset();
+        timer.tick(2.0);
+        assert!(timer.just_finished());
+    }
+
+    #[test]
+    fn scheduled_callbacks_come_due_on_time() {
+        let mut timers = Timers::new();
+        let once = timers.after(1.0, |_| ());
+        let every = timers.every(0.5, |_| ());
+        let due_ids = |due: &[ScheduledCallback]| due.iter().map(|due| due.id).collect::<Vec<_>>();
+
+        let due = timers.take_due(0.5);
+        assert_eq!(due_ids(&due), vec![every]);
+        timers.reschedule(due);
+
+        let due = timers.take_due(1.0);
+        assert_eq!(due_ids(&due), vec![once, every]);
+        timers.reschedule(due);
+        assert!(!timers.is_scheduled(once));
+        assert!(timers.is_scheduled(every));
+
+        // missed ticks are skipped instead of all running at once
+        let due = timers.take_due(3.0);
+        assert_eq!(due_ids(&due), vec![every]);
+        timers.reschedule(due);
+        assert!(timers.take_due(3.25).is_empty());
+
+        timers.cancel(every);
+        assert!(timers.take_due(10.0).is_empty());
+    }
+}
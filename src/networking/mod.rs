@@ -4,6 +4,7 @@ use steamworks::*;
 pub struct NetworkingResource {
     pub client: Client,
     pub single: SingleClient,
+    pub callback_timer: Timer,
 }
 impl_resource!(NetworkingResource, 7);
 
@@ -38,46 +39,74 @@ impl NetworkingResource {
             friends.request_user_information(f.id(), true);
         }
 
-        Ok(Self { client, single })
+        Ok(Self {
+            client,
+            single,
+            callback_timer: Timer::repeating(CALLBACK_INTERVAL),
+        })
     }
 }
 
-const FRAMES_PER_CALLBACK_RUN: u32 = 3;
-static mut FRAMES_SINCE_CALLBACK_RUN: u32 = 0;
+// seconds between steam callback runs
+const CALLBACK_INTERVAL: f64 = 0.05;
 
 create_system!(run_callbacks, get_run_callbacks_system;
-    uses NetworkingResource);
-async fn run_callbacks(game_state: &mut GameState, _t: f64, _dt: f64) {
-    unsafe {
-        // the system is never run in parallel with itself, so `FRAMES_SINCE_CALLBACK_RUN` doesn't
-        // need to be atomized
-        FRAMES_SINCE_CALLBACK_RUN += 1;
-        if FRAMES_SINCE_CALLBACK_RUN < FRAMES_PER_CALLBACK_RUN {
-            return;
-        }
+    uses NetworkingResource, Time);
+async fn run_callbacks(game_state: &mut GameState, _t: f64, dt: f64) -> SystemResult {
+    let dt = real_delta_or(game_state, dt);
+
+    let networking = game_state.require_resource_mut::<NetworkingResource>()?;
+    networking.callback_timer.tick(dt);
 
-        FRAMES_SINCE_CALLBACK_RUN = 0;
+    if networking.callback_timer.just_finished() {
+        networking.single.run_callbacks();
+    }
+
+    Ok(())
+}
+
+pub struct NetworkingPlugin;
+
+impl Plugin for NetworkingPlugin {
+    fn build(&self, app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+        let error_policy = if app.conf.exit_on_networking_error {
+            ErrorPolicy::Close
+        } else {
+            ErrorPolicy::Log
+        };
+
+        app.add_system(
+            get_init_networking_system().with_error_policy(error_policy),
+            SystemType::Init,
+        )
+        .add_system(get_shutdown_networking_system(), SystemType::Close);
+        Ok(())
     }
-    let networking = game_state.get_resource::<NetworkingResource>().unwrap();
-    networking.single.run_callbacks();
 }
 
 create_system!(init_networking, get_init_networking_system;
     uses GameState, NetworkingResource);
-async fn init_networking(game_state: &mut GameState, _t: f64, _dt: f64) {
-    match NetworkingResource::new() {
-        Ok(networking) => {
-            let scheduler = game_state.get_scheduler_mut();
-            //add systems here
-            scheduler.add_system(get_run_callbacks_system(), SystemType::Update);
-
-            game_state.add_resource(networking);
-        }
-        Err(e) => {
-            eprintln!("Error initializing networking: {}", e);
-            if game_state.conf.exit_on_networking_error {
-                game_state.close();
-            }
-        }
+async fn init_networking(game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+    let networking =
+        NetworkingResource::new().map_err(|e| format!("Error initializing networking: {}", e))?;
+
+    let scheduler = game_state.get_scheduler_mut();
+    //add systems here
+    scheduler.add_system(get_run_callbacks_system(), SystemType::Update);
+
+    game_state.add_resource(networking);
+
+    Ok(())
+}
+
+//uses GameState because it removes a resource
+create_system!(shutdown_networking, get_shutdown_networking_system;
+    uses GameState, NetworkingResource);
+async fn shutdown_networking(game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+    // dropping the client disconnects from steam once pending callbacks are handled
+    if let Some(networking) = game_state.remove_resource::<NetworkingResource>() {
+        networking.single.run_callbacks();
     }
+
+    Ok(())
 }
@@ -0,0 +1,400 @@
+use crate::*;
+use glium::uniforms::{UniformValue, Uniforms};
+use glium::Surface;
+use std::ops::Range;
+
+// draws the ui's rectangles, images and text
+pub const UI_SHADER: &str = "ui";
+
+// the width of a text field's cursor and a slider's track
+const LINE_WIDTH: f32 = 2.0;
+const SLIDER_HANDLE_WIDTH: f32 = 10.0;
+
+#[derive(Copy, Clone, Debug)]
+pub struct UiVertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+glium::implement_vertex!(UiVertex, position, uv, color);
+
+// what a quad's color is multiplied with, the numbers are the shader's `mode`
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    Solid,
+    Image(&'a Texture),
+    Glyphs(&'a Font),
+}
+
+impl Source<'_> {
+    fn same(&self, other: &Source) -> bool {
+        match (self, other) {
+            (Source::Solid, Source::Solid) => true,
+            (Source::Image(a), Source::Image(b)) => std::ptr::eq(*a, *b),
+            (Source::Glyphs(a), Source::Glyphs(b)) => std::ptr::eq(*a, *b),
+            _ => false,
+        }
+    }
+}
+
+// consecutive quads that can be drawn together
+struct DrawBatch<'a> {
+    source: Source<'a>,
+    clip: Option<Rect>,
+    vertices: Range<usize>,
+}
+
+#[derive(Default)]
+struct DrawList<'a> {
+    vertices: Vec<UiVertex>,
+    batches: Vec<DrawBatch<'a>>,
+}
+
+impl<'a> DrawList<'a> {
+    fn quad(
+        &mut self,
+        source: Source<'a>,
+        clip: Option<Rect>,
+        rect: Rect,
+        uv: [[f32; 2]; 2],
+        color: [f32; 4],
+    ) {
+        if rect.width <= 0.0 || rect.height <= 0.0 || color[3] <= 0.0 {
+            return;
+        }
+
+        let start = self.vertices.len();
+        let corner = |x: usize, y: usize| UiVertex {
+            position: [
+                rect.x + rect.width * x as f32,
+                rect.y + rect.height * y as f32,
+            ],
+            uv: [uv[x][0], uv[y][1]],
+            color,
+        };
+        self.vertices.extend([
+            corner(0, 0),
+            corner(0, 1),
+            corner(1, 1),
+            corner(0, 0),
+            corner(1, 1),
+            corner(1, 0),
+        ]);
+
+        match self.batches.last_mut() {
+            Some(batch) if batch.source.same(&source) && batch.clip == clip => {
+                batch.vertices.end = self.vertices.len();
+            }
+            _ => self.batches.push(DrawBatch {
+                source,
+                clip,
+                vertices: start..self.vertices.len(),
+            }),
+        }
+    }
+
+    fn rect(&mut self, clip: Option<Rect>, rect: Rect, color: [f32; 4]) {
+        self.quad(Source::Solid, clip, rect, [[0.0; 2], [1.0; 2]], color);
+    }
+
+    fn border(&mut self, clip: Option<Rect>, rect: Rect, width: f32, color: [f32; 4]) {
+        if width <= 0.0 {
+            return;
+        }
+
+        let Rect {
+            x,
+            y,
+            width: w,
+            height: h,
+        } = rect;
+        self.rect(clip, Rect::new(x, y, w, width), color);
+        self.rect(clip, Rect::new(x, y + h - width, w, width), color);
+        self.rect(clip, Rect::new(x, y + width, width, h - 2.0 * width), color);
+        self.rect(
+            clip,
+            Rect::new(x + w - width, y + width, width, h - 2.0 * width),
+            color,
+        );
+    }
+
+    // a line of text vertically centred in `rect`, returns where it ends
+    fn text(
+        &mut self,
+        font: &'a Font,
+        clip: Option<Rect>,
+        rect: Rect,
+        text: &str,
+        style: &Style,
+        color: [f32; 4],
+    ) -> f32 {
+        let x = match style.align {
+            TextAlign::Left => rect.x,
+            TextAlign::Center => rect.x + rect.width / 2.0,
+            TextAlign::Right => rect.x + rect.width,
+        };
+        // whole pixels keep the glyphs sharp
+        let origin = [x.round(), (rect.y + rect.height / 2.0).round()];
+
+        let mut end = origin[0];
+        for quad in font.layout(text, style.font_size, style.align, VerticalAlign::Middle) {
+            self.quad(
+                Source::Glyphs(font),
+                clip,
+                Rect::new(
+                    origin[0] + quad.min[0],
+                    origin[1] + quad.min[1],
+                    quad.max[0] - quad.min[0],
+                    quad.max[1] - quad.min[1],
+                ),
+                [quad.uv_min, quad.uv_max],
+                color,
+            );
+            end = end.max(origin[0] + quad.max[0]);
+        }
+
+        match style.align {
+            TextAlign::Left => origin[0] + font.measure(text, style.font_size)[0],
+            _ => end,
+        }
+    }
+}
+
+struct UiUniforms<'a> {
+    matrix: [[f32; 4]; 4],
+    mode: i32,
+    image: UniformValue<'a>,
+}
+
+impl Uniforms for UiUniforms<'_> {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F) {
+        visit("matrix", UniformValue::Mat4(self.matrix));
+        visit("mode", UniformValue::SignedInt(self.mode));
+        visit("image", self.image);
+    }
+}
+
+impl Ui {
+    // returns the number of draw calls made
+    pub fn draw<S: Surface>(
+        &self,
+        render_resource: &RenderResource,
+        program: &glium::Program,
+        frame: &mut S,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        if self.hidden {
+            return Ok(0);
+        }
+
+        let list = self.draw_list(render_resource.default_font.as_ref());
+        if list.vertices.is_empty() {
+            return Ok(0);
+        }
+
+        // solid quads sample a white texture so every batch can use the same shader
+        let white = render_resource
+            .default_textures
+            .iter()
+            .find(|(name, _)| *name == "base_color_map")
+            .map(|(_, texture)| texture)
+            .ok_or("No default white texture")?;
+
+        let (width, height) = frame.get_dimensions();
+        let buffer = glium::VertexBuffer::new(render_resource.facade(), &list.vertices)?;
+
+        for batch in &list.batches {
+            let (mode, image) = match batch.source {
+                Source::Solid => (0, white.as_uniform()),
+                Source::Image(texture) => (0, texture.as_uniform()),
+                Source::Glyphs(font) => (
+                    1,
+                    UniformValue::Texture2d(
+                        &font.atlas,
+                        Some(glium::uniforms::SamplerBehavior {
+                            magnify_filter: glium::uniforms::MagnifySamplerFilter::Linear,
+                            minify_filter: glium::uniforms::MinifySamplerFilter::LinearMipmapLinear,
+                            ..Default::default()
+                        }),
+                    ),
+                ),
+            };
+            let uniforms = UiUniforms {
+                matrix: screen_matrix(width, height),
+                mode,
+                image,
+            };
+
+            // OpenGL counts rows from the bottom
+            let scissor = batch.clip.map(|clip| glium::Rect {
+                left: clip.x.max(0.0) as u32,
+                bottom: (height as f32 - clip.y - clip.height).max(0.0) as u32,
+                width: clip.width.max(0.0) as u32,
+                height: clip.height.max(0.0) as u32,
+            });
+            let params = glium::DrawParameters {
+                blend: glium::Blend::alpha_blending(),
+                scissor,
+                ..Default::default()
+            };
+
+            frame.draw(
+                buffer
+                    .slice(batch.vertices.clone())
+                    .ok_or("Ui vertices out of range")?,
+                glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+                program,
+                &uniforms,
+                &params,
+            )?;
+        }
+
+        Ok(list.batches.len())
+    }
+
+    fn draw_list<'a>(&'a self, default_font: Option<&'a Font>) -> DrawList<'a> {
+        let mut list = DrawList::default();
+
+        for id in self.visible_widgets() {
+            let Some(widget) = self.get(id) else {
+                continue;
+            };
+            let style = &widget.style;
+            let rect = widget.rect;
+            let inner = rect.shrink(widget.layout.padding);
+            let font = style.font.as_deref().or(default_font);
+
+            // disabled widgets are faded out
+            let fade = |color: [f32; 4]| {
+                let alpha = if widget.enabled { 1.0 } else { 0.5 };
+                [color[0], color[1], color[2], color[3] * alpha]
+            };
+
+            let interactive = widget.enabled && widget.is_interactive();
+            let background = if interactive && self.pressed == Some(id) {
+                style.pressed
+            } else if interactive && self.hovered == Some(id) {
+                style.hovered
+            } else {
+                style.background
+            };
+            list.rect(None, rect, fade(background));
+
+            let focused = self.focused == Some(id);
+            let border = if focused {
+                style.foreground
+            } else {
+                style.border
+            };
+            list.border(None, rect, style.border_width, fade(border));
+
+            match &widget.kind {
+                WidgetKind::Panel => (),
+                WidgetKind::Label { .. }
+                | WidgetKind::Button { .. }
+                | WidgetKind::TextField { .. } => {
+                    let (Some(font), Some((text, placeholder))) = (font, widget.display_text())
+                    else {
+                        continue;
+                    };
+                    let color = if placeholder {
+                        [
+                            style.foreground[0],
+                            style.foreground[1],
+                            style.foreground[2],
+                            style.foreground[3] * 0.5,
+                        ]
+                    } else {
+                        style.foreground
+                    };
+
+                    let end = list.text(font, Some(rect), inner, text, style, fade(color));
+                    if focused {
+                        let end = if placeholder { inner.x } else { end };
+                        let height = line_height(style, Some(font));
+                        let caret = Rect::new(
+                            end + 1.0,
+                            inner.y + (inner.height - height) / 2.0,
+                            LINE_WIDTH,
+                            height,
+                        );
+                        list.rect(Some(rect), caret, style.foreground);
+                    }
+                }
+                WidgetKind::Slider { value, min, max } => {
+                    let t = if max > min {
+                        (value - min) / (max - min)
+                    } else {
+                        0.0
+                    };
+                    let track_y = inner.y + (inner.height - LINE_WIDTH) / 2.0;
+                    list.rect(
+                        None,
+                        Rect::new(inner.x, track_y, inner.width, LINE_WIDTH),
+                        fade(style.border),
+                    );
+                    list.rect(
+                        None,
+                        Rect::new(inner.x, track_y, inner.width * t, LINE_WIDTH),
+                        fade(style.foreground),
+                    );
+
+                    let handle_x = inner.x + inner.width * t - SLIDER_HANDLE_WIDTH / 2.0;
+                    list.rect(
+                        None,
+                        Rect::new(handle_x, inner.y, SLIDER_HANDLE_WIDTH, inner.height),
+                        fade(style.foreground),
+                    );
+                }
+                WidgetKind::Image { texture } => {
+                    list.quad(
+                        Source::Image(texture),
+                        None,
+                        inner,
+                        // textures are loaded with their bottom row first
+                        [[0.0, 1.0], [1.0, 0.0]],
+                        fade([1.0; 4]),
+                    );
+                }
+                WidgetKind::List {
+                    items,
+                    selected,
+                    scroll,
+                } => {
+                    let Some(font) = font else {
+                        continue;
+                    };
+                    let row_height = row_height(style, Some(font));
+                    let clip = Some(inner);
+
+                    let first = scroll.floor() as usize;
+                    let rows = (inner.height / row_height).ceil() as usize + 1;
+                    for (i, item) in items.iter().enumerate().skip(first).take(rows) {
+                        let row = Rect::new(
+                            inner.x,
+                            inner.y + (i as f32 - scroll) * row_height,
+                            inner.width,
+                            row_height,
+                        );
+
+                        if *selected == Some(i) {
+                            let mut color = style.foreground;
+                            color[3] *= 0.25;
+                            list.rect(clip, row, fade(color));
+                        }
+                        list.text(
+                            font,
+                            clip,
+                            row.shrink(2.0),
+                            item,
+                            style,
+                            fade(style.foreground),
+                        );
+                    }
+                }
+            }
+        }
+
+        list
+    }
+}
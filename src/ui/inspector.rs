@@ -0,0 +1,608 @@
+use crate::*;
+use std::any::Any;
+use winit::event::VirtualKeyCode;
+
+const FONT_SIZE: f32 = 16.0;
+// longer component and resource descriptions are cut off
+const MAX_LINE_LENGTH: usize = 160;
+
+// component type 23
+// an overlay listing the game's entities, components, resources and systems, toggled with
+// `toggle_key`
+pub struct Inspector {
+    pub open: bool,
+    pub toggle_key: VirtualKeyCode,
+    // id of the entity whose components are shown
+    pub selected: Option<usize>,
+
+    // entity ids in the order the entity list shows them
+    listed: Vec<usize>,
+    // built the first time the inspector is opened
+    widgets: Option<InspectorWidgets>,
+    toggle_was_down: bool,
+}
+impl_resource!(Inspector, 23);
+
+impl Default for Inspector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone)]
+struct InspectorWidgets {
+    root: WidgetId,
+    summary: WidgetId,
+    entities: WidgetId,
+    spawn: WidgetId,
+    despawn: WidgetId,
+    components: WidgetId,
+    transform: NumberFields,
+    camera: NumberFields,
+    resources: WidgetId,
+    systems: WidgetId,
+}
+
+// text fields editing a component's numbers, in rows of three
+#[derive(Clone)]
+struct NumberFields {
+    panel: WidgetId,
+    fields: Vec<WidgetId>,
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Inspector {
+            open: false,
+            toggle_key: VirtualKeyCode::F1,
+            selected: None,
+            listed: Vec::new(),
+            widgets: None,
+            toggle_was_down: false,
+        }
+    }
+
+    pub fn with_toggle_key(mut self, key: VirtualKeyCode) -> Self {
+        self.toggle_key = key;
+        self
+    }
+
+    fn update(&mut self, ui: &mut Ui, game_state: &mut GameState, resources: Vec<String>) {
+        let toggle_down = game_state
+            .get_resource::<InputHandler>()
+            .is_some_and(|input| input.is_down(self.toggle_key));
+        if toggle_down && !self.toggle_was_down {
+            self.open = !self.open;
+        }
+        self.toggle_was_down = toggle_down;
+
+        if self.open && self.widgets.is_none() {
+            self.widgets = Some(InspectorWidgets::build(ui));
+        }
+        let Some(widgets) = self.widgets.clone() else {
+            return;
+        };
+
+        if let Some(root) = ui.get_mut(widgets.root) {
+            root.visible = self.open;
+        }
+        if !self.open {
+            return;
+        }
+
+        if ui.changed(widgets.entities) {
+            self.selected = ui
+                .selected(widgets.entities)
+                .and_then(|row| self.listed.get(row).copied());
+        }
+
+        if ui.clicked(widgets.spawn) {
+            let entity = game_state.create_entity(format!("Entity {}", game_state.next_entity_id));
+            let transform = Transform::new([0.0; 3], [0.0; 3], [1.0; 3]);
+            entity.add_component(game_state, transform, Transform::get_component_type());
+            self.selected = Some(entity.id as usize);
+        }
+        if ui.clicked(widgets.despawn) {
+            if let Some(id) = self.selected.take() {
+                game_state.despawn_entity(id);
+            }
+        }
+
+        self.show_entities(ui, &widgets, game_state);
+        self.show_selected(ui, &widgets, game_state);
+        ui.set_items(widgets.resources, resources);
+        ui.set_items(widgets.systems, system_timings(game_state.get_scheduler()));
+
+        let mut summary = format!("{} entities", self.listed.len());
+        if let Some(time) = game_state.get_resource::<Time>() {
+            summary += &format!("  {:.2} ms frame", time.real.delta * 1000.0);
+        }
+        if let Some(renderer) = game_state.get_resource::<RenderResource>() {
+            let stats = renderer.stats;
+            summary += &format!(
+                "  {} draw calls  {} drawn  {} culled",
+                stats.draw_calls, stats.drawn, stats.culled
+            );
+        }
+        ui.set_text(widgets.summary, &summary);
+    }
+
+    fn show_entities(&mut self, ui: &mut Ui, widgets: &InspectorWidgets, game_state: &GameState) {
+        self.listed.clear();
+        let mut items = Vec::new();
+
+        for entity in &game_state.entities {
+            let entity = unsafe { &*entity.get() };
+            if entity.alive {
+                self.listed.push(entity.id as usize);
+                items.push(format!("{:>4} {}", entity.id, entity.name));
+            }
+        }
+
+        self.selected = self.selected.filter(|id| self.listed.contains(id));
+        let row = self
+            .selected
+            .and_then(|id| self.listed.iter().position(|listed| *listed == id));
+
+        ui.set_items(widgets.entities, items);
+        ui.select(widgets.entities, row);
+        if let Some(despawn) = ui.get_mut(widgets.despawn) {
+            despawn.enabled = self.selected.is_some();
+        }
+    }
+
+    fn show_selected(&self, ui: &mut Ui, widgets: &InspectorWidgets, game_state: &GameState) {
+        let entity = self.selected.and_then(|id| game_state.get_entity(id));
+
+        let components = entity.as_ref().map_or(Vec::new(), |entity| {
+            entity
+                .components
+                .iter()
+                .map(|component| {
+                    let component = unsafe { &*component.get() };
+                    truncate(format!("{:?}", component.component))
+                })
+                .collect()
+        });
+        ui.set_items(widgets.components, components);
+
+        let (mut transform, mut camera) = (None, None);
+        for component in entity.map_or(&[][..], |entity| &entity.components) {
+            let component = unsafe { &mut *component.get() };
+            let any = &mut *component.component as &mut dyn Any;
+            match component.component_type {
+                t if t == Transform::get_component_type() => transform = any.downcast_mut(),
+                t if t == Camera::get_component_type() => camera = any.downcast_mut(),
+                _ => (),
+            }
+        }
+
+        widgets.transform.show(
+            ui,
+            transform.map(|transform: &mut Transform| {
+                let Transform {
+                    position,
+                    rotation,
+                    scale,
+                } = transform;
+                position.iter_mut().chain(rotation).chain(scale).collect()
+            }),
+        );
+        widgets.camera.show(
+            ui,
+            camera.map(|camera: &mut Camera| {
+                let Camera {
+                    position,
+                    rotation,
+                    fov,
+                    near,
+                    far,
+                    ..
+                } = camera;
+                position
+                    .iter_mut()
+                    .chain(rotation)
+                    .chain([fov, near, far])
+                    .collect()
+            }),
+        );
+    }
+}
+
+impl InspectorWidgets {
+    fn build(ui: &mut Ui) -> Self {
+        let root = ui.add(
+            None,
+            Widget::panel()
+                .with_style(Style::default())
+                .with_layout(Layout {
+                    padding: 8.0,
+                    align: Align::Stretch,
+                    ..Layout::column(6.0)
+                })
+                .with_size(Size::Pixels(760.0), Size::Auto)
+                .with_anchor(Anchor::TOP_LEFT.with_offset(10.0, 10.0)),
+        );
+        let summary = ui.add(Some(root), label(""));
+
+        let columns = ui.add(Some(root), Widget::panel().with_layout(Layout::row(8.0)));
+
+        let left = ui.add(
+            Some(columns),
+            Widget::panel()
+                .with_layout(Layout {
+                    align: Align::Stretch,
+                    ..Layout::column(4.0)
+                })
+                .with_size(Size::Pixels(260.0), Size::Auto),
+        );
+        ui.add(Some(left), label("Entities"));
+        let entities = ui.add(Some(left), list(268.0));
+        let buttons = ui.add(Some(left), Widget::panel().with_layout(Layout::row(4.0)));
+        let spawn = ui.add(Some(buttons), button("Spawn"));
+        let despawn = ui.add(Some(buttons), button("Despawn"));
+
+        let right = ui.add(
+            Some(columns),
+            Widget::panel().with_layout(Layout {
+                align: Align::Stretch,
+                grow: 1.0,
+                ..Layout::column(4.0)
+            }),
+        );
+        ui.add(Some(right), label("Components"));
+        let components = ui.add(Some(right), list(100.0));
+        let transform =
+            NumberFields::build(ui, right, "Transform", ["position", "rotation", "scale"]);
+        let camera = NumberFields::build(
+            ui,
+            right,
+            "Camera",
+            ["position", "rotation", "fov near far"],
+        );
+
+        ui.add(Some(root), label("Resources"));
+        let resources = ui.add(Some(root), list(100.0));
+        ui.add(Some(root), label("Systems"));
+        let systems = ui.add(Some(root), list(140.0));
+
+        InspectorWidgets {
+            root,
+            summary,
+            entities,
+            spawn,
+            despawn,
+            components,
+            transform,
+            camera,
+            resources,
+            systems,
+        }
+    }
+}
+
+impl NumberFields {
+    fn build(ui: &mut Ui, parent: WidgetId, title: &str, rows: [&str; 3]) -> Self {
+        let panel = ui.add(
+            Some(parent),
+            Widget::panel().with_layout(Layout::column(4.0)),
+        );
+        ui.add(Some(panel), label(title));
+
+        let mut fields = Vec::new();
+        for name in rows {
+            let row = ui.add(
+                Some(panel),
+                Widget::panel().with_layout(Layout {
+                    align: Align::Center,
+                    ..Layout::row(4.0)
+                }),
+            );
+            ui.add(
+                Some(row),
+                label(name).with_size(Size::Pixels(110.0), Size::Auto),
+            );
+            for _ in 0..3 {
+                fields.push(
+                    ui.add(
+                        Some(row),
+                        Widget::text_field("")
+                            .with_style(style())
+                            .with_size(Size::Pixels(110.0), Size::Auto),
+                    ),
+                );
+            }
+        }
+
+        NumberFields { panel, fields }
+    }
+
+    // hidden when the selected entity doesn't have the component
+    fn show(&self, ui: &mut Ui, values: Option<Vec<&mut f32>>) {
+        if let Some(panel) = ui.get_mut(self.panel) {
+            panel.visible = values.is_some();
+        }
+
+        for (id, value) in self.fields.iter().zip(values.into_iter().flatten()) {
+            if ui.changed(*id) {
+                // half typed numbers leave the value alone until they parse
+                if let Some(parsed) = ui.text(*id).and_then(|text| text.trim().parse().ok()) {
+                    *value = parsed;
+                }
+            } else if ui.focused() != Some(*id) {
+                ui.set_text(*id, &format!("{:.3}", value));
+            }
+        }
+    }
+}
+
+create_system!(update_inspector, get_update_inspector_system;
+    uses GameState, Inspector, Ui, InputHandler);
+async fn update_inspector(game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+    // described before the inspector and ui are taken out so they're included
+    let open = game_state.require_resource::<Inspector>()?.open;
+    let resources = if open {
+        resource_descriptions(game_state)
+    } else {
+        Vec::new()
+    };
+
+    // taken out so they can be changed while the entities and other resources are
+    game_state.with_resource_removed(|inspector: &mut Inspector, game_state| {
+        game_state.with_resource_removed(|ui: &mut Ui, game_state| {
+            inspector.update(ui, game_state, resources)
+        })
+    })??;
+
+    Ok(())
+}
+
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+        app.add_resource(Inspector::new())
+            .add_system(get_update_inspector_system(), SystemType::Update);
+        Ok(())
+    }
+}
+
+fn resource_descriptions(game_state: &GameState) -> Vec<String> {
+    let mut descriptions: Vec<String> = game_state
+        .resources
+        .iter()
+        // `Debug` output already starts with the name
+        .map(|resource| match resource.inspect() {
+            Some(state) => truncate(state),
+            None => resource.name().to_string(),
+        })
+        .collect();
+    descriptions.sort();
+    descriptions
+}
+
+// how long every update and fixed update system took the last time it ran
+fn system_timings(scheduler: &Scheduler) -> Vec<String> {
+    let update = scheduler.systems(SystemType::Update).iter();
+    let fixed = scheduler.systems(SystemType::FixedUpdate).iter();
+
+    update
+        .map(|system| (system, ""))
+        .chain(fixed.map(|system| (system, " (fixed)")))
+        .map(|(system, kind)| {
+            let disabled = if system.is_enabled() {
+                ""
+            } else {
+                " (disabled)"
+            };
+            format!(
+                "{:>8.3} ms  {}{}{}",
+                system.last_duration().as_secs_f64() * 1000.0,
+                system.name,
+                kind,
+                disabled
+            )
+        })
+        .collect()
+}
+
+fn truncate(mut line: String) -> String {
+    if let Some((end, _)) = line.char_indices().nth(MAX_LINE_LENGTH) {
+        line.truncate(end);
+        line.push_str("...");
+    }
+    line
+}
+
+fn style() -> Style {
+    Style {
+        font_size: FONT_SIZE,
+        ..Default::default()
+    }
+}
+
+fn label(text: &str) -> Widget {
+    Widget::label(text).with_style(Style {
+        font_size: FONT_SIZE,
+        ..Style::transparent()
+    })
+}
+
+fn button(text: &str) -> Widget {
+    Widget::button(text)
+        .with_style(style())
+        .with_align(TextAlign::Center)
+}
+
+fn list(height: f32) -> Widget {
+    Widget::list(Vec::new())
+        .with_style(style())
+        // long rows are cut off instead of widening the inspector
+        .with_size(Size::Fraction(1.0), Size::Pixels(height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use std::sync::Arc;
+
+    fn game_state(scheduler: &mut Scheduler) -> GameState {
+        let mut game_state = GameState::new(scheduler, Arc::new(Config::default()));
+        game_state.add_resource(InputHandler::new());
+        game_state.add_resource(Ui::new());
+        game_state.add_resource(Inspector {
+            open: true,
+            ..Inspector::new()
+        });
+
+        let teapot = game_state.create_entity("Teapot".to_string());
+        let transform = Transform::new([1.0, 2.0, 3.0], [0.0; 3], [1.0; 3]);
+        teapot.add_component(&mut game_state, transform, Transform::get_component_type());
+        game_state.create_entity("Empty".to_string());
+
+        game_state
+    }
+
+    // updates the ui and then the inspector, like a frame of the game would
+    fn frame(game_state: &mut GameState, mouse: (f64, f64), down: bool, text: &str) {
+        let input = game_state.require_resource_mut::<InputHandler>().unwrap();
+        input.mouse_pos = mouse;
+        input.mouse_buttons = down as u8;
+        input.text_input = text.to_string();
+
+        game_state
+            .with_resource_removed(|ui: &mut Ui, game_state| {
+                let input = game_state.require_resource::<InputHandler>().unwrap();
+                ui.update(input, Rect::new(0.0, 0.0, 1280.0, 720.0), None);
+            })
+            .unwrap();
+        futures::executor::block_on(update_inspector(game_state, 0.0, 0.0)).unwrap();
+    }
+
+    fn click(game_state: &mut GameState, mouse: (f64, f64)) {
+        frame(game_state, mouse, true, "");
+        frame(game_state, mouse, false, "");
+    }
+
+    fn centre(game_state: &GameState, id: WidgetId) -> (f64, f64) {
+        let rect = game_state
+            .require_resource::<Ui>()
+            .unwrap()
+            .get(id)
+            .unwrap()
+            .rect;
+        (
+            (rect.x + rect.width / 2.0) as f64,
+            (rect.y + rect.height / 2.0) as f64,
+        )
+    }
+
+    fn widgets(game_state: &GameState) -> InspectorWidgets {
+        let inspector = game_state.require_resource::<Inspector>().unwrap();
+        inspector.widgets.clone().unwrap()
+    }
+
+    fn rows(game_state: &GameState, id: WidgetId) -> Vec<String> {
+        match &game_state
+            .require_resource::<Ui>()
+            .unwrap()
+            .get(id)
+            .unwrap()
+            .kind
+        {
+            WidgetKind::List { items, .. } => items.clone(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn selected(game_state: &GameState) -> Option<usize> {
+        game_state.require_resource::<Inspector>().unwrap().selected
+    }
+
+    #[test]
+    fn entity_list_selects_spawns_and_despawns() {
+        let mut scheduler = Scheduler::new(0.01);
+        let mut game_state = game_state(&mut scheduler);
+
+        // the widgets are built on the first frame and laid out on the next
+        frame(&mut game_state, (0.0, 0.0), false, "");
+        frame(&mut game_state, (0.0, 0.0), false, "");
+        let widgets = widgets(&game_state);
+        assert_eq!(
+            rows(&game_state, widgets.entities),
+            vec!["   0 Teapot", "   1 Empty"]
+        );
+
+        // rows are 16 pixels of text and some spacing tall
+        let list = game_state
+            .require_resource::<Ui>()
+            .unwrap()
+            .get(widgets.entities)
+            .unwrap()
+            .rect;
+        let second_row = ((list.x + 10.0) as f64, (list.y + 16.0 * 1.5) as f64);
+        click(&mut game_state, second_row);
+        assert_eq!(selected(&game_state), Some(1));
+        assert!(rows(&game_state, widgets.components).is_empty());
+
+        let spawn = centre(&game_state, widgets.spawn);
+        click(&mut game_state, spawn);
+        assert_eq!(selected(&game_state), Some(2));
+        assert_eq!(rows(&game_state, widgets.entities).len(), 3);
+        assert_eq!(rows(&game_state, widgets.components).len(), 1);
+
+        let despawn = centre(&game_state, widgets.despawn);
+        click(&mut game_state, despawn);
+        assert_eq!(selected(&game_state), None);
+        assert_eq!(
+            rows(&game_state, widgets.entities),
+            vec!["   0 Teapot", "   1 Empty"]
+        );
+        assert!(!game_state.get_entity(2).unwrap().alive);
+    }
+
+    #[test]
+    fn number_fields_edit_the_selected_transform() {
+        let mut scheduler = Scheduler::new(0.01);
+        let mut game_state = game_state(&mut scheduler);
+        game_state
+            .require_resource_mut::<Inspector>()
+            .unwrap()
+            .selected = Some(0);
+
+        frame(&mut game_state, (0.0, 0.0), false, "");
+        frame(&mut game_state, (0.0, 0.0), false, "");
+        let widgets = widgets(&game_state);
+        let field = widgets.transform.fields[0];
+        let text = |game_state: &GameState| {
+            let ui = game_state.require_resource::<Ui>().unwrap();
+            ui.text(field).unwrap().to_string()
+        };
+        let position = |game_state: &GameState| {
+            let transform = game_state.get_components::<Transform>(Transform::get_component_type());
+            transform[0].position
+        };
+
+        assert_eq!(text(&game_state), "1.000");
+        let ui = game_state.require_resource::<Ui>().unwrap();
+        assert!(ui.get(widgets.transform.panel).unwrap().visible);
+        assert!(!ui.get(widgets.camera.panel).unwrap().visible);
+
+        let field_centre = centre(&game_state, field);
+        click(&mut game_state, field_centre);
+        frame(&mut game_state, field_centre, false, &"\u{8}".repeat(5));
+        assert_eq!(position(&game_state), [1.0, 2.0, 3.0]);
+
+        // half typed numbers don't change anything until they parse
+        frame(&mut game_state, field_centre, false, "-");
+        assert_eq!(position(&game_state), [1.0, 2.0, 3.0]);
+        frame(&mut game_state, field_centre, false, "5.5");
+        assert_eq!(position(&game_state), [-5.5, 2.0, 3.0]);
+        assert_eq!(text(&game_state), "-5.5");
+
+        // the field shows the value again once it loses focus
+        click(&mut game_state, (1200.0, 700.0));
+        assert_eq!(text(&game_state), "-5.500");
+    }
+}
@@ -0,0 +1,441 @@
+use crate::*;
+
+// pixels from the top left of the window
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        point[0] >= self.x
+            && point[1] >= self.y
+            && point[0] < self.x + self.width
+            && point[1] < self.y + self.height
+    }
+
+    pub fn shrink(&self, amount: f32) -> Rect {
+        Rect {
+            x: self.x + amount,
+            y: self.y + amount,
+            width: (self.width - 2.0 * amount).max(0.0),
+            height: (self.height - 2.0 * amount).max(0.0),
+        }
+    }
+
+    // the part of both rects, empty if they don't overlap
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        Rect {
+            x,
+            y,
+            width: ((self.x + self.width).min(other.x + other.width) - x).max(0.0),
+            height: ((self.y + self.height).min(other.y + other.height) - y).max(0.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Size {
+    // fits the content
+    #[default]
+    Auto,
+    Pixels(f32),
+    // of the parent's size inside its padding
+    Fraction(f32),
+}
+
+impl Size {
+    fn resolve(&self, parent: f32, content: f32) -> f32 {
+        match *self {
+            Size::Auto => content,
+            Size::Pixels(pixels) => pixels,
+            Size::Fraction(fraction) => parent * fraction,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Direction {
+    Row,
+    #[default]
+    Column,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Start,
+    Center,
+    End,
+    // fills the cross axis, only for children with an `Auto` size
+    Stretch,
+}
+
+// places a widget relative to its parent instead of after its siblings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anchor {
+    // the point of the parent to place the widget at, 0 to 1 from the top left
+    pub anchor: [f32; 2],
+    // the point of the widget placed there
+    pub pivot: [f32; 2],
+    // pixels added afterwards
+    pub offset: [f32; 2],
+}
+
+impl Anchor {
+    pub const TOP_LEFT: Anchor = Anchor::at([0.0, 0.0]);
+    pub const TOP: Anchor = Anchor::at([0.5, 0.0]);
+    pub const TOP_RIGHT: Anchor = Anchor::at([1.0, 0.0]);
+    pub const LEFT: Anchor = Anchor::at([0.0, 0.5]);
+    pub const CENTER: Anchor = Anchor::at([0.5, 0.5]);
+    pub const RIGHT: Anchor = Anchor::at([1.0, 0.5]);
+    pub const BOTTOM_LEFT: Anchor = Anchor::at([0.0, 1.0]);
+    pub const BOTTOM: Anchor = Anchor::at([0.5, 1.0]);
+    pub const BOTTOM_RIGHT: Anchor = Anchor::at([1.0, 1.0]);
+
+    // the widget's matching point is placed at the parent's, so it stays inside
+    pub const fn at(anchor: [f32; 2]) -> Self {
+        Anchor {
+            anchor,
+            pivot: anchor,
+            offset: [0.0; 2],
+        }
+    }
+
+    pub const fn with_offset(mut self, x: f32, y: f32) -> Self {
+        self.offset = [x, y];
+        self
+    }
+}
+
+// flex style, children without an anchor are placed one after another along `direction`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Layout {
+    pub width: Size,
+    pub height: Size,
+    pub direction: Direction,
+    pub padding: f32,
+    // between children
+    pub gap: f32,
+    // this widget's share of the space its parent has left along its direction
+    pub grow: f32,
+    // where children go across `direction`
+    pub align: Align,
+    // where children go along `direction` when they don't fill it
+    pub justify: Align,
+    // roots without an anchor are placed at the top left of the window
+    pub anchor: Option<Anchor>,
+}
+
+impl Layout {
+    pub fn row(gap: f32) -> Self {
+        Layout {
+            direction: Direction::Row,
+            gap,
+            ..Default::default()
+        }
+    }
+
+    pub fn column(gap: f32) -> Self {
+        Layout {
+            direction: Direction::Column,
+            gap,
+            ..Default::default()
+        }
+    }
+}
+
+impl Ui {
+    // sizes every visible widget and places it inside `screen`
+    pub(crate) fn layout(&mut self, screen: Rect, default_font: Option<&Font>) {
+        let mut measured = vec![[0.0; 2]; self.widgets.len()];
+        for root in self.roots.clone() {
+            self.measure(root, default_font, &mut measured);
+        }
+
+        for root in self.roots.clone() {
+            let Some(widget) = self.get(root).filter(|widget| widget.visible) else {
+                continue;
+            };
+            let rect = place(
+                &widget.layout,
+                screen,
+                measured[root.0],
+                widget.layout.anchor.unwrap_or(Anchor::TOP_LEFT),
+            );
+            self.arrange(root, rect, &measured);
+        }
+    }
+
+    // the size every widget needs for its content, fractions of the parent count as nothing
+    fn measure(&self, id: WidgetId, default_font: Option<&Font>, measured: &mut [[f32; 2]]) {
+        let Some(widget) = self.get(id).filter(|widget| widget.visible) else {
+            return;
+        };
+        for child in &widget.children {
+            self.measure(*child, default_font, measured);
+        }
+
+        let layout = &widget.layout;
+        let font = widget.style.font.as_deref().or(default_font);
+        let text_size = |text: &str| {
+            font.map_or([0.0, widget.style.font_size], |font| {
+                font.measure(text, widget.style.font_size)
+            })
+        };
+        let line = line_height(&widget.style, default_font);
+
+        let content = match &widget.kind {
+            WidgetKind::Panel => {
+                let main = match layout.direction {
+                    Direction::Row => 0,
+                    Direction::Column => 1,
+                };
+                let mut size = [0.0; 2];
+                let mut count = 0usize;
+
+                for (child, child_widget) in self.flow_children(widget) {
+                    let child_size = [
+                        child_widget.layout.width.resolve(0.0, measured[child.0][0]),
+                        child_widget
+                            .layout
+                            .height
+                            .resolve(0.0, measured[child.0][1]),
+                    ];
+                    size[main] += child_size[main];
+                    size[1 - main] = f32::max(size[1 - main], child_size[1 - main]);
+                    count += 1;
+                }
+                size[main] += layout.gap * count.saturating_sub(1) as f32;
+                size
+            }
+            WidgetKind::Label { text } | WidgetKind::Button { text } => text_size(text),
+            WidgetKind::TextField { .. } | WidgetKind::Slider { .. } => [200.0, line],
+            WidgetKind::Image { texture } => {
+                let (width, height) = texture_dimensions(texture);
+                [width as f32, height as f32]
+            }
+            WidgetKind::List { items, .. } => {
+                let width = items
+                    .iter()
+                    .map(|item| text_size(item)[0])
+                    .fold(100.0, f32::max);
+                let rows = items.len().clamp(1, 6) as f32;
+                [width, rows * row_height(&widget.style, default_font)]
+            }
+        };
+
+        measured[id.0] = [
+            content[0] + 2.0 * layout.padding,
+            content[1] + 2.0 * layout.padding,
+        ];
+    }
+
+    // visible children that aren't anchored
+    fn flow_children<'a>(
+        &'a self,
+        widget: &'a Widget,
+    ) -> impl Iterator<Item = (WidgetId, &'a Widget)> {
+        widget
+            .children
+            .iter()
+            .filter_map(|child| Some((*child, self.get(*child)?)))
+            .filter(|(_, child)| child.visible && child.layout.anchor.is_none())
+    }
+
+    fn arrange(&mut self, id: WidgetId, rect: Rect, measured: &[[f32; 2]]) {
+        let Some(widget) = self.get_mut(id) else {
+            return;
+        };
+        widget.rect = rect;
+
+        let layout = widget.layout;
+        let children = widget.children.clone();
+        let inner = rect.shrink(layout.padding);
+
+        let (main, inner_main, inner_cross) = match layout.direction {
+            Direction::Row => (0, inner.width, inner.height),
+            Direction::Column => (1, inner.height, inner.width),
+        };
+
+        // sizes along and across the direction of every child in the flow
+        let mut flow = Vec::new();
+        for child in &children {
+            let Some(widget) = self.get(*child).filter(|widget| widget.visible) else {
+                continue;
+            };
+
+            if let Some(anchor) = widget.layout.anchor {
+                let rect = place(&widget.layout, inner, measured[child.0], anchor);
+                self.arrange(*child, rect, measured);
+                continue;
+            }
+
+            let sizes = [widget.layout.width, widget.layout.height];
+            let size_main = sizes[main].resolve(inner_main, measured[child.0][main]);
+            let size_cross = match (layout.align, sizes[1 - main]) {
+                (Align::Stretch, Size::Auto) => inner_cross,
+                (_, size) => size.resolve(inner_cross, measured[child.0][1 - main]),
+            };
+            flow.push((*child, size_main, size_cross, widget.layout.grow));
+        }
+
+        let gaps = layout.gap * flow.len().saturating_sub(1) as f32;
+        let mut left = inner_main - gaps - flow.iter().map(|child| child.1).sum::<f32>();
+        let grow: f32 = flow.iter().map(|child| child.3).sum();
+        if grow > 0.0 && left > 0.0 {
+            for child in flow.iter_mut() {
+                child.1 += left * child.3 / grow;
+            }
+            left = 0.0;
+        }
+
+        let mut position = match layout.justify {
+            Align::Start | Align::Stretch => 0.0,
+            Align::Center => left.max(0.0) / 2.0,
+            Align::End => left.max(0.0),
+        };
+        for (child, size_main, size_cross, _) in flow {
+            let cross = match layout.align {
+                Align::Start | Align::Stretch => 0.0,
+                Align::Center => (inner_cross - size_cross) / 2.0,
+                Align::End => inner_cross - size_cross,
+            };
+
+            let rect = match layout.direction {
+                Direction::Row => {
+                    Rect::new(inner.x + position, inner.y + cross, size_main, size_cross)
+                }
+                Direction::Column => {
+                    Rect::new(inner.x + cross, inner.y + position, size_cross, size_main)
+                }
+            };
+            self.arrange(child, rect, measured);
+            position += size_main + layout.gap;
+        }
+    }
+}
+
+// where an anchored widget goes inside `parent`
+fn place(layout: &Layout, parent: Rect, measured: [f32; 2], anchor: Anchor) -> Rect {
+    let width = layout.width.resolve(parent.width, measured[0]);
+    let height = layout.height.resolve(parent.height, measured[1]);
+
+    Rect::new(
+        parent.x + anchor.anchor[0] * parent.width - anchor.pivot[0] * width + anchor.offset[0],
+        parent.y + anchor.anchor[1] * parent.height - anchor.pivot[1] * height + anchor.offset[1],
+        width,
+        height,
+    )
+}
+
+pub(crate) fn texture_dimensions(texture: &Texture) -> (u32, u32) {
+    match &texture.texture {
+        TextureData::Srgb(texture) => texture.dimensions(),
+        TextureData::Linear(texture) => texture.dimensions(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect_of(ui: &Ui, id: WidgetId) -> Rect {
+        ui.get(id).unwrap().rect
+    }
+
+    #[test]
+    fn intersect_keeps_the_overlap() {
+        let a = Rect::new(0.0, 0.0, 100.0, 50.0);
+
+        assert_eq!(
+            a.intersect(&Rect::new(60.0, 20.0, 100.0, 100.0)),
+            Rect::new(60.0, 20.0, 40.0, 30.0)
+        );
+        assert_eq!(
+            a.intersect(&Rect::new(10.0, 10.0, 5.0, 5.0)),
+            Rect::new(10.0, 10.0, 5.0, 5.0)
+        );
+
+        let apart = a.intersect(&Rect::new(200.0, 100.0, 10.0, 10.0));
+        assert_eq!((apart.width, apart.height), (0.0, 0.0));
+    }
+
+    #[test]
+    fn row_places_children_after_each_other_and_grows() {
+        let mut ui = Ui::new();
+        let root = ui.add(
+            None,
+            Widget::panel()
+                .with_layout(Layout {
+                    padding: 5.0,
+                    align: Align::Stretch,
+                    ..Layout::row(10.0)
+                })
+                .with_size(Size::Pixels(300.0), Size::Pixels(100.0)),
+        );
+        let fixed = ui.add(
+            Some(root),
+            Widget::panel().with_size(Size::Pixels(50.0), Size::Pixels(20.0)),
+        );
+        let grown = ui.add(
+            Some(root),
+            Widget::panel().with_layout(Layout {
+                grow: 1.0,
+                ..Default::default()
+            }),
+        );
+
+        ui.layout(Rect::new(0.0, 0.0, 800.0, 600.0), None);
+
+        assert_eq!(rect_of(&ui, root), Rect::new(0.0, 0.0, 300.0, 100.0));
+        assert_eq!(rect_of(&ui, fixed), Rect::new(5.0, 5.0, 50.0, 20.0));
+        assert_eq!(rect_of(&ui, grown), Rect::new(65.0, 5.0, 230.0, 90.0));
+    }
+
+    #[test]
+    fn column_justifies_and_anchors() {
+        let mut ui = Ui::new();
+        let root = ui.add(
+            None,
+            Widget::panel()
+                .with_layout(Layout {
+                    align: Align::Center,
+                    justify: Align::Center,
+                    ..Layout::column(0.0)
+                })
+                .with_size(Size::Pixels(400.0), Size::Pixels(200.0))
+                .with_anchor(Anchor::CENTER),
+        );
+        let centered = ui.add(
+            Some(root),
+            Widget::panel().with_size(Size::Fraction(0.25), Size::Pixels(40.0)),
+        );
+        let corner = ui.add(
+            Some(root),
+            Widget::panel()
+                .with_size(Size::Pixels(20.0), Size::Pixels(20.0))
+                .with_anchor(Anchor::BOTTOM_RIGHT.with_offset(-5.0, -5.0)),
+        );
+
+        ui.layout(Rect::new(0.0, 0.0, 800.0, 600.0), None);
+
+        assert_eq!(rect_of(&ui, root), Rect::new(200.0, 200.0, 400.0, 200.0));
+        assert_eq!(rect_of(&ui, centered), Rect::new(350.0, 280.0, 100.0, 40.0));
+        assert_eq!(rect_of(&ui, corner), Rect::new(575.0, 375.0, 20.0, 20.0));
+    }
+}
@@ -0,0 +1,535 @@
+use crate::*;
+use winit::event::MouseButton;
+
+pub mod draw;
+pub use draw::*;
+
+pub mod inspector;
+pub use inspector::*;
+
+pub mod layout;
+pub use layout::*;
+
+pub mod widget;
+pub use widget::*;
+
+// list rows are this many lines of text tall
+const LIST_ROW_HEIGHT: f32 = 1.3;
+
+// component type 22
+// widgets added once and kept between frames, laid out and updated from `InputHandler` every
+// frame and drawn over everything else
+#[derive(Debug, Default)]
+pub struct Ui {
+    // hides and disables every widget
+    pub hidden: bool,
+
+    pub(crate) widgets: Vec<Option<Widget>>,
+    pub(crate) roots: Vec<WidgetId>,
+
+    hovered: Option<WidgetId>,
+    // the widget the mouse was pressed on, until it is released
+    pressed: Option<WidgetId>,
+    // the text field typing goes to
+    focused: Option<WidgetId>,
+    mouse_was_down: bool,
+}
+impl_resource!(Ui, 22);
+
+impl Ui {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // roots are placed in the window, later widgets are drawn over earlier ones
+    pub fn add(&mut self, parent: Option<WidgetId>, mut widget: Widget) -> WidgetId {
+        let id = WidgetId(self.widgets.len());
+        widget.parent = parent.filter(|parent| self.get(*parent).is_some());
+        widget.children.clear();
+
+        match widget.parent.and_then(|parent| self.get_mut(parent)) {
+            Some(parent) => parent.children.push(id),
+            None => self.roots.push(id),
+        }
+        self.widgets.push(Some(widget));
+        id
+    }
+
+    // removes the widget and everything in it
+    pub fn remove(&mut self, id: WidgetId) {
+        let Some(widget) = self.widgets.get_mut(id.0).and_then(Option::take) else {
+            return;
+        };
+
+        match widget.parent.and_then(|parent| self.get_mut(parent)) {
+            Some(parent) => parent.children.retain(|child| *child != id),
+            None => self.roots.retain(|root| *root != id),
+        }
+        for child in widget.children {
+            self.remove(child);
+        }
+
+        for state in [&mut self.hovered, &mut self.pressed, &mut self.focused] {
+            if *state == Some(id) {
+                *state = None;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Ui {
+            hidden: self.hidden,
+            ..Default::default()
+        };
+    }
+
+    pub fn get(&self, id: WidgetId) -> Option<&Widget> {
+        self.widgets.get(id.0).and_then(Option::as_ref)
+    }
+
+    pub fn get_mut(&mut self, id: WidgetId) -> Option<&mut Widget> {
+        self.widgets.get_mut(id.0).and_then(Option::as_mut)
+    }
+
+    pub fn children(&self, id: WidgetId) -> &[WidgetId] {
+        self.get(id).map_or(&[], |widget| &widget.children)
+    }
+
+    pub fn clicked(&self, id: WidgetId) -> bool {
+        self.get(id).is_some_and(|widget| widget.events.clicked)
+    }
+
+    pub fn changed(&self, id: WidgetId) -> bool {
+        self.get(id).is_some_and(|widget| widget.events.changed)
+    }
+
+    pub fn submitted(&self, id: WidgetId) -> bool {
+        self.get(id).is_some_and(|widget| widget.events.submitted)
+    }
+
+    pub fn text(&self, id: WidgetId) -> Option<&str> {
+        self.get(id).and_then(Widget::text)
+    }
+
+    pub fn set_text(&mut self, id: WidgetId, new_text: &str) {
+        if let Some(widget) = self.get_mut(id) {
+            match &mut widget.kind {
+                WidgetKind::Label { text }
+                | WidgetKind::Button { text }
+                | WidgetKind::TextField { text, .. } => {
+                    text.clear();
+                    text.push_str(new_text);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    pub fn value(&self, id: WidgetId) -> Option<f32> {
+        match self.get(id)?.kind {
+            WidgetKind::Slider { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn selected(&self, id: WidgetId) -> Option<usize> {
+        match self.get(id)?.kind {
+            WidgetKind::List { selected, .. } => selected,
+            _ => None,
+        }
+    }
+
+    // replaces a list's rows, keeping the selection and scroll where they still fit
+    pub fn set_items(&mut self, id: WidgetId, new_items: Vec<String>) {
+        if let Some(WidgetKind::List {
+            items,
+            selected,
+            scroll,
+        }) = self.get_mut(id).map(|widget| &mut widget.kind)
+        {
+            *selected = selected.filter(|selected| *selected < new_items.len());
+            *scroll = scroll.min(new_items.len().saturating_sub(1) as f32);
+            *items = new_items;
+        }
+    }
+
+    pub fn select(&mut self, id: WidgetId, row: Option<usize>) {
+        if let Some(WidgetKind::List {
+            items, selected, ..
+        }) = self.get_mut(id).map(|widget| &mut widget.kind)
+        {
+            *selected = row.filter(|row| *row < items.len());
+        }
+    }
+
+    pub fn hovered(&self) -> Option<WidgetId> {
+        self.hovered
+    }
+
+    pub fn focused(&self) -> Option<WidgetId> {
+        self.focused
+    }
+
+    // the mouse is over a widget, so the game shouldn't react to it
+    pub fn wants_mouse(&self) -> bool {
+        self.hovered.is_some() || self.pressed.is_some()
+    }
+
+    // a text field is being typed in, so the game shouldn't react to keys
+    pub fn wants_keyboard(&self) -> bool {
+        self.focused.is_some()
+    }
+
+    // every visible widget, parents before their children, in the order they're drawn
+    pub(crate) fn visible_widgets(&self) -> Vec<WidgetId> {
+        let mut visible = Vec::new();
+        let mut stack: Vec<WidgetId> = self.roots.iter().rev().copied().collect();
+
+        while let Some(id) = stack.pop() {
+            let Some(widget) = self.get(id).filter(|widget| widget.visible) else {
+                continue;
+            };
+            visible.push(id);
+            stack.extend(widget.children.iter().rev());
+        }
+
+        visible
+    }
+
+    fn update(&mut self, input: &InputHandler, screen: Rect, default_font: Option<&Font>) {
+        for widget in self.widgets.iter_mut().flatten() {
+            widget.events = WidgetEvents::default();
+        }
+
+        if self.hidden {
+            self.hovered = None;
+            self.pressed = None;
+            self.focused = None;
+            return;
+        }
+
+        self.layout(screen, default_font);
+
+        let mouse = [input.mouse_pos.0 as f32, input.mouse_pos.1 as f32];
+        let down = input.is_mouse_down(MouseButton::Left);
+        let (just_pressed, released) = (down && !self.mouse_was_down, !down && self.mouse_was_down);
+        self.mouse_was_down = down;
+
+        // the last widget drawn under the mouse is on top
+        self.hovered = self.visible_widgets().into_iter().rev().find(|id| {
+            self.get(*id)
+                .is_some_and(|widget| widget.rect.contains(mouse))
+        });
+
+        if just_pressed {
+            self.pressed = self.hovered.filter(|id| {
+                self.get(*id)
+                    .is_some_and(|widget| widget.enabled && widget.is_interactive())
+            });
+            self.focused = self.pressed.filter(|id| {
+                self.get(*id)
+                    .is_some_and(|widget| matches!(widget.kind, WidgetKind::TextField { .. }))
+            });
+
+            if let Some(id) = self.pressed {
+                self.select_row(id, mouse, default_font);
+            }
+        }
+
+        if let Some(id) = self.pressed.filter(|_| down) {
+            self.drag_slider(id, mouse);
+        }
+
+        if released {
+            if let Some(id) = self.pressed.take().filter(|id| Some(*id) == self.hovered) {
+                if let Some(widget) = self.get_mut(id) {
+                    widget.events.clicked = matches!(widget.kind, WidgetKind::Button { .. });
+                }
+            }
+        }
+
+        if input.scroll_delta != 0.0 {
+            if let Some(id) = self.hovered {
+                self.scroll_list(id, input.scroll_delta as f32, default_font);
+            }
+        }
+
+        if let Some(id) = self.focused {
+            self.type_text(id, &input.text_input);
+        }
+    }
+
+    fn select_row(&mut self, id: WidgetId, mouse: [f32; 2], default_font: Option<&Font>) {
+        let Some(widget) = self.get_mut(id) else {
+            return;
+        };
+        let row_height = row_height(&widget.style, default_font);
+        let inner = widget.rect.shrink(widget.layout.padding);
+
+        if let WidgetKind::List {
+            items,
+            selected,
+            scroll,
+        } = &mut widget.kind
+        {
+            let row = ((mouse[1] - inner.y) / row_height + *scroll).floor();
+            if row >= 0.0 && (row as usize) < items.len() && *selected != Some(row as usize) {
+                *selected = Some(row as usize);
+                widget.events.changed = true;
+            }
+        }
+    }
+
+    fn drag_slider(&mut self, id: WidgetId, mouse: [f32; 2]) {
+        let Some(widget) = self.get_mut(id) else {
+            return;
+        };
+        let inner = widget.rect.shrink(widget.layout.padding);
+
+        if let WidgetKind::Slider { value, min, max } = &mut widget.kind {
+            let t = ((mouse[0] - inner.x) / inner.width.max(1.0)).clamp(0.0, 1.0);
+            let new_value = *min + (*max - *min) * t;
+            if new_value != *value {
+                *value = new_value;
+                widget.events.changed = true;
+            }
+        }
+    }
+
+    fn scroll_list(&mut self, id: WidgetId, lines: f32, default_font: Option<&Font>) {
+        let Some(widget) = self.get_mut(id) else {
+            return;
+        };
+        let row_height = row_height(&widget.style, default_font);
+        let rows = widget.rect.shrink(widget.layout.padding).height / row_height;
+
+        if let WidgetKind::List { items, scroll, .. } = &mut widget.kind {
+            let max_scroll = (items.len() as f32 - rows).max(0.0);
+            *scroll = (*scroll - lines).clamp(0.0, max_scroll);
+        }
+    }
+
+    fn type_text(&mut self, id: WidgetId, input: &str) {
+        let Some(widget) = self.get_mut(id).filter(|widget| widget.enabled) else {
+            return;
+        };
+
+        if let WidgetKind::TextField {
+            text, max_length, ..
+        } = &mut widget.kind
+        {
+            for c in input.chars() {
+                match c {
+                    // backspace
+                    '\u{8}' => widget.events.changed |= text.pop().is_some(),
+                    '\r' | '\n' => widget.events.submitted = true,
+                    c if c.is_control() => (),
+                    c if text.chars().count() < *max_length => {
+                        text.push(c);
+                        widget.events.changed = true;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+// the height of one line of a widget's text
+pub(crate) fn line_height(style: &Style, default_font: Option<&Font>) -> f32 {
+    style
+        .font
+        .as_deref()
+        .or(default_font)
+        .map_or(style.font_size, |font| font.measure("", style.font_size)[1])
+}
+
+pub(crate) fn row_height(style: &Style, default_font: Option<&Font>) -> f32 {
+    line_height(style, default_font) * LIST_ROW_HEIGHT
+}
+
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+        app.add_resource(Ui::new())
+            .add_system(get_update_ui_system(), SystemType::Update);
+        Ok(())
+    }
+}
+
+//uses GameState because it removes a resource
+create_system!(update_ui, get_update_ui_system;
+    uses GameState, Ui, InputHandler, RenderResource);
+async fn update_ui(game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+    // taken out so it can be changed while the renderer's font is borrowed
+    game_state.with_resource_removed(|ui: &mut Ui, game_state| {
+        if let (Some(input), Some(renderer)) = (
+            game_state.get_resource::<InputHandler>(),
+            game_state.get_resource::<RenderResource>(),
+        ) {
+            let (width, height) = renderer.dimensions();
+            let screen = Rect::new(0.0, 0.0, width as f32, height as f32);
+            ui.update(input, screen, renderer.default_font.as_ref());
+        }
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::event::ElementState;
+
+    // a frame of input, `down` is the left mouse button
+    fn frame(ui: &mut Ui, mouse: (f64, f64), down: bool, text: &str, scroll: f64) {
+        let mut input = InputHandler::new();
+        input.mouse_pos = mouse;
+        input.text_input = text.to_string();
+        input.scroll_delta = scroll;
+        if down {
+            input.handle_mouse_button(MouseButton::Left, ElementState::Pressed);
+        }
+        ui.update(&input, Rect::new(0.0, 0.0, 800.0, 600.0), None);
+    }
+
+    fn click(ui: &mut Ui, mouse: (f64, f64)) {
+        frame(ui, mouse, true, "", 0.0);
+        frame(ui, mouse, false, "", 0.0);
+    }
+
+    #[test]
+    fn slider_range_is_normalised() {
+        let value = |slider: Widget| match slider.kind {
+            WidgetKind::Slider { value, min, max } => (value, min, max),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(value(Widget::slider(10.0, 0.0, 20.0)), (10.0, 0.0, 10.0));
+        assert_eq!(value(Widget::slider(0.0, 10.0, f32::NAN)), (0.0, 0.0, 10.0));
+        assert_eq!(
+            value(Widget::slider(f32::NAN, 10.0, 5.0)),
+            (10.0, 10.0, 10.0)
+        );
+        assert_eq!(
+            value(Widget::slider(f32::NAN, f32::NAN, 5.0)),
+            (0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn dragging_a_slider_follows_the_mouse() {
+        let mut ui = Ui::new();
+        let slider = ui.add(
+            None,
+            Widget::slider(0.0, 10.0, 5.0).with_size(Size::Pixels(100.0), Size::Pixels(20.0)),
+        );
+
+        // moving over it without pressing leaves it alone
+        frame(&mut ui, (80.0, 10.0), false, "", 0.0);
+        assert_eq!(ui.value(slider), Some(5.0));
+
+        frame(&mut ui, (25.0, 10.0), true, "", 0.0);
+        assert_eq!(ui.value(slider), Some(2.5));
+        assert!(ui.changed(slider));
+        assert!(ui.wants_mouse());
+
+        // keeps following once the mouse leaves it, clamped to the range
+        frame(&mut ui, (300.0, 200.0), true, "", 0.0);
+        assert_eq!(ui.value(slider), Some(10.0));
+
+        frame(&mut ui, (300.0, 200.0), false, "", 0.0);
+        frame(&mut ui, (0.0, 10.0), false, "", 0.0);
+        assert_eq!(ui.value(slider), Some(10.0));
+        assert!(!ui.changed(slider));
+    }
+
+    #[test]
+    fn lists_scroll_and_select_rows() {
+        let mut ui = Ui::new();
+        let items = (0..20).map(|i| format!("Item {}", i)).collect();
+        // rows are 26 pixels tall with the default 20 pixel font
+        let list = ui.add(
+            None,
+            Widget::list(items).with_size(Size::Pixels(100.0), Size::Pixels(104.0)),
+        );
+        let scroll = |ui: &Ui| match ui.get(list).unwrap().kind {
+            WidgetKind::List { scroll, .. } => scroll,
+            _ => unreachable!(),
+        };
+
+        // only the hovered list scrolls
+        frame(&mut ui, (200.0, 50.0), false, "", -3.0);
+        assert_eq!(scroll(&ui), 0.0);
+
+        frame(&mut ui, (50.0, 50.0), false, "", -3.0);
+        assert_eq!(scroll(&ui), 3.0);
+
+        click(&mut ui, (50.0, 30.0));
+        assert_eq!(ui.selected(list), Some(4));
+
+        // stops with the last row at the bottom
+        frame(&mut ui, (50.0, 50.0), false, "", -100.0);
+        assert_eq!(scroll(&ui), 16.0);
+        frame(&mut ui, (50.0, 50.0), false, "", 100.0);
+        assert_eq!(scroll(&ui), 0.0);
+    }
+
+    #[test]
+    fn buttons_click_when_released_over_them() {
+        let mut ui = Ui::new();
+        let button = ui.add(
+            None,
+            Widget::button("Go").with_size(Size::Pixels(100.0), Size::Pixels(40.0)),
+        );
+
+        frame(&mut ui, (50.0, 20.0), true, "", 0.0);
+        assert!(!ui.clicked(button));
+        frame(&mut ui, (50.0, 20.0), false, "", 0.0);
+        assert!(ui.clicked(button));
+
+        // only for one frame
+        frame(&mut ui, (50.0, 20.0), false, "", 0.0);
+        assert!(!ui.clicked(button));
+
+        // dragging off before releasing cancels the click
+        frame(&mut ui, (50.0, 20.0), true, "", 0.0);
+        frame(&mut ui, (300.0, 20.0), false, "", 0.0);
+        assert!(!ui.clicked(button));
+
+        ui.get_mut(button).unwrap().enabled = false;
+        click(&mut ui, (50.0, 20.0));
+        assert!(!ui.clicked(button));
+    }
+
+    #[test]
+    fn text_fields_take_typing_once_focused() {
+        let mut ui = Ui::new();
+        let field = ui.add(
+            None,
+            Widget::text_field("Name").with_size(Size::Pixels(200.0), Size::Pixels(40.0)),
+        );
+
+        frame(&mut ui, (50.0, 20.0), false, "ignored", 0.0);
+        assert_eq!(ui.text(field), Some(""));
+
+        click(&mut ui, (50.0, 20.0));
+        assert!(ui.wants_keyboard());
+
+        frame(&mut ui, (50.0, 20.0), false, "ab\u{8}c", 0.0);
+        assert_eq!(ui.text(field), Some("ac"));
+        assert!(ui.changed(field));
+        assert!(!ui.submitted(field));
+
+        frame(&mut ui, (50.0, 20.0), false, "\r", 0.0);
+        assert!(ui.submitted(field));
+        assert!(!ui.changed(field));
+
+        // clicking anywhere else takes the focus away
+        click(&mut ui, (400.0, 300.0));
+        frame(&mut ui, (400.0, 300.0), false, "d", 0.0);
+        assert_eq!(ui.text(field), Some("ac"));
+        assert!(!ui.wants_keyboard());
+    }
+}
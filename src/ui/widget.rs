@@ -0,0 +1,255 @@
+use crate::*;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WidgetId(pub(crate) usize);
+
+#[derive(Debug, Clone)]
+pub enum WidgetKind {
+    // only holds other widgets, drawn as its background
+    Panel,
+    Label {
+        text: String,
+    },
+    Button {
+        text: String,
+    },
+    Slider {
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+    // single line, typing goes to the end of the text
+    TextField {
+        text: String,
+        placeholder: String,
+        max_length: usize,
+    },
+    Image {
+        texture: Rc<Texture>,
+    },
+    // rows of text that can be selected and scrolled through
+    List {
+        items: Vec<String>,
+        selected: Option<usize>,
+        // rows scrolled past at the top
+        scroll: f32,
+    },
+}
+
+// colors are linear, like every other color the renderer takes
+#[derive(Debug, Clone)]
+pub struct Style {
+    pub background: [f32; 4],
+    // replaces `background` while the mouse is over the widget or holding it down
+    pub hovered: [f32; 4],
+    pub pressed: [f32; 4],
+    // text, slider handles and list selections
+    pub foreground: [f32; 4],
+    pub border: [f32; 4],
+    pub border_width: f32,
+    pub font_size: f32,
+    // `DEFAULT_FONT` when `None`
+    pub font: Option<Rc<Font>>,
+    pub align: TextAlign,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            background: [0.015, 0.015, 0.02, 0.9],
+            hovered: [0.04, 0.04, 0.05, 0.95],
+            pressed: [0.005, 0.005, 0.008, 1.0],
+            foreground: [0.8, 0.8, 0.8, 1.0],
+            border: [0.1, 0.1, 0.12, 1.0],
+            border_width: 1.0,
+            font_size: 20.0,
+            font: None,
+            align: TextAlign::Left,
+        }
+    }
+}
+
+impl Style {
+    // nothing drawn behind the content, for labels and layout panels
+    pub fn transparent() -> Self {
+        Style {
+            background: [0.0; 4],
+            hovered: [0.0; 4],
+            pressed: [0.0; 4],
+            border_width: 0.0,
+            ..Default::default()
+        }
+    }
+}
+
+// what happened to a widget during the last update
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WidgetEvents {
+    // pressed and released over a button
+    pub clicked: bool,
+    // a slider, text field or list changed its value
+    pub changed: bool,
+    // enter was pressed in a text field
+    pub submitted: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Widget {
+    pub kind: WidgetKind,
+    pub layout: Layout,
+    pub style: Style,
+    // hidden widgets and their children aren't drawn, laid out or interacted with
+    pub visible: bool,
+    // disabled widgets are drawn but ignore input
+    pub enabled: bool,
+
+    // set by the last update
+    pub rect: Rect,
+    pub events: WidgetEvents,
+
+    pub(crate) parent: Option<WidgetId>,
+    pub(crate) children: Vec<WidgetId>,
+}
+
+impl Widget {
+    pub fn new(kind: WidgetKind) -> Self {
+        let style = match kind {
+            WidgetKind::Panel | WidgetKind::Label { .. } | WidgetKind::Image { .. } => {
+                Style::transparent()
+            }
+            _ => Style::default(),
+        };
+
+        Widget {
+            kind,
+            layout: Layout::default(),
+            style,
+            visible: true,
+            enabled: true,
+            rect: Rect::default(),
+            events: WidgetEvents::default(),
+            parent: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn panel() -> Self {
+        Self::new(WidgetKind::Panel)
+    }
+
+    pub fn label(text: &str) -> Self {
+        Self::new(WidgetKind::Label {
+            text: text.to_string(),
+        })
+    }
+
+    pub fn button(text: &str) -> Self {
+        Self::new(WidgetKind::Button {
+            text: text.to_string(),
+        })
+        .with_layout(Layout {
+            padding: 8.0,
+            ..Default::default()
+        })
+        .with_align(TextAlign::Center)
+    }
+
+    // a reversed range is swapped, NaN bounds give way to the other bound and a NaN value to min
+    pub fn slider(min: f32, max: f32, value: f32) -> Self {
+        let (min, max) = match (min.min(max), min.max(max)) {
+            (min, _) if min.is_nan() => (0.0, 0.0),
+            range => range,
+        };
+        Self::new(WidgetKind::Slider {
+            value: value.max(min).min(max),
+            min,
+            max,
+        })
+    }
+
+    pub fn text_field(placeholder: &str) -> Self {
+        Self::new(WidgetKind::TextField {
+            text: String::new(),
+            placeholder: placeholder.to_string(),
+            max_length: usize::MAX,
+        })
+        .with_layout(Layout {
+            padding: 6.0,
+            ..Default::default()
+        })
+    }
+
+    pub fn image(texture: Rc<Texture>) -> Self {
+        Self::new(WidgetKind::Image { texture })
+    }
+
+    pub fn list(items: Vec<String>) -> Self {
+        Self::new(WidgetKind::List {
+            items,
+            selected: None,
+            scroll: 0.0,
+        })
+    }
+
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_size(mut self, width: Size, height: Size) -> Self {
+        self.layout.width = width;
+        self.layout.height = height;
+        self
+    }
+
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.layout.anchor = Some(anchor);
+        self
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.style.align = align;
+        self
+    }
+
+    pub fn with_background(mut self, color: [f32; 4]) -> Self {
+        self.style.background = color;
+        self
+    }
+
+    // the label, button or text field's text
+    pub fn text(&self) -> Option<&str> {
+        match &self.kind {
+            WidgetKind::Label { text }
+            | WidgetKind::Button { text }
+            | WidgetKind::TextField { text, .. } => Some(text),
+            _ => None,
+        }
+    }
+
+    // the text drawn for labels, buttons and text fields, showing the placeholder of empty fields
+    pub(crate) fn display_text(&self) -> Option<(&str, bool)> {
+        match &self.kind {
+            WidgetKind::TextField {
+                text, placeholder, ..
+            } if text.is_empty() => Some((placeholder, true)),
+            _ => self.text().map(|text| (text, false)),
+        }
+    }
+
+    pub(crate) fn is_interactive(&self) -> bool {
+        matches!(
+            self.kind,
+            WidgetKind::Button { .. }
+                | WidgetKind::Slider { .. }
+                | WidgetKind::TextField { .. }
+                | WidgetKind::List { .. }
+        )
+    }
+}
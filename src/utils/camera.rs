@@ -1,4 +1,5 @@
 use crate::core::*;
+use crate::utils::math::{frustum::Frustum, matrix::Matrix4};
 use std::any::Any;
 
 // component type 3
@@ -10,9 +11,136 @@ pub struct Camera {
     pub aspect_ratio: f32,
     pub near: f32,
     pub far: f32,
+
+    // the part of the window drawn to
+    pub viewport: Viewport,
+    // cameras are drawn from the lowest order up, later ones over earlier ones
+    pub order: i32,
+    pub clear: CameraClear,
+    // inactive cameras aren't drawn
+    pub active: bool,
 }
 impl_component!(Camera, 3);
 
+// how far past the window's edge a viewport may reach, sums of fractions like thirds round up
+const EDGE_TOLERANCE: f32 = 1e-6;
+
+// a part of the window, 0 to 1 from the top left like the ui
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    pub const FULL: Viewport = Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    pub fn new(
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let viewport = Viewport {
+            x,
+            y,
+            width,
+            height,
+        };
+
+        if [x, y, width, height]
+            .iter()
+            .any(|value| !(0.0..=1.0).contains(value))
+        {
+            return Err(format!("Viewport has to be within 0 to 1, got {:?}", viewport).into());
+        }
+        if x + width > 1.0 + EDGE_TOLERANCE || y + height > 1.0 + EDGE_TOLERANCE {
+            return Err(format!("Viewport reaches past the window, got {:?}", viewport).into());
+        }
+
+        Ok(viewport)
+    }
+
+    // the viewport's pixels in a window of `dimensions`, rows counted from the bottom like OpenGL
+    pub fn to_pixels(&self, dimensions: (u32, u32)) -> glium::Rect {
+        let (width, height) = (dimensions.0 as f32, dimensions.1 as f32);
+        // rounding the edges instead of the sizes keeps neighbouring viewports from overlapping.
+        // max and min rather than clamp, so fields set to NaN give an empty rect instead of a panic
+        let left = (self.x * width).round().max(0.0).min(width);
+        let right = ((self.x + self.width) * width).round().max(left).min(width);
+        let top = (self.y * height).round().max(0.0).min(height);
+        let bottom = ((self.y + self.height) * height)
+            .round()
+            .max(top)
+            .min(height);
+
+        glium::Rect {
+            left: left as u32,
+            bottom: (height - bottom) as u32,
+            width: (right - left) as u32,
+            height: (bottom - top) as u32,
+        }
+    }
+
+    pub fn aspect_ratio(&self, dimensions: (u32, u32)) -> f32 {
+        let pixels = self.to_pixels(dimensions);
+        pixels.width.max(1) as f32 / pixels.height.max(1) as f32
+    }
+
+    // equal parts of the window for `count` players, side by side for two and a grid filled row
+    // by row for more
+    pub fn split_screen(count: usize) -> Vec<Viewport> {
+        let columns = match count {
+            0..=2 => count.max(1),
+            _ => (count as f32).sqrt().ceil() as usize,
+        };
+        let rows = count.div_ceil(columns).max(1);
+        let (width, height) = (1.0 / columns as f32, 1.0 / rows as f32);
+
+        (0..count)
+            .map(|i| Viewport {
+                x: (i % columns) as f32 * width,
+                y: (i / columns) as f32 * height,
+                width,
+                height,
+            })
+            .collect()
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Viewport::FULL
+    }
+}
+
+// what is cleared in a camera's viewport before it draws
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraClear {
+    // a linear color and the depth
+    Color([f32; 4]),
+    // keeps the colors earlier cameras drew, for overlays
+    Depth,
+    Nothing,
+}
+
+// gives each camera an equal part of the window, in the order they're given
+pub fn split_screen(cameras: Vec<&mut Camera>, dimensions: (u32, u32)) {
+    let viewports = Viewport::split_screen(cameras.len());
+
+    for (camera, viewport) in cameras.into_iter().zip(viewports) {
+        camera.viewport = viewport;
+        camera.fit_viewport(dimensions);
+    }
+}
+
 impl Camera {
     pub const fn new(
         position: [f32; 3],
@@ -29,9 +157,33 @@ impl Camera {
             aspect_ratio,
             near,
             far,
+            viewport: Viewport::FULL,
+            order: 0,
+            clear: CameraClear::Color([0.0, 0.0, 0.0, 1.0]),
+            active: true,
         }
     }
 
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    pub fn with_order(mut self, order: i32) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn with_clear(mut self, clear: CameraClear) -> Self {
+        self.clear = clear;
+        self
+    }
+
+    // matches the aspect ratio to the viewport's shape in a window of `dimensions`
+    pub fn fit_viewport(&mut self, dimensions: (u32, u32)) {
+        self.aspect_ratio = self.viewport.aspect_ratio(dimensions);
+    }
+
     pub fn get_proj(&self) -> [[f32; 4]; 4] {
         let fov = self.fov.to_radians();
         let aspect_ratio = self.aspect_ratio;
@@ -48,6 +200,20 @@ impl Camera {
         ]
     }
 
+    // where the camera is in world space, `position` is the view translation
+    pub fn get_world_position(&self) -> [f32; 3] {
+        Matrix4(self.get_view())
+            .inverse()
+            .map_or(self.position, |inverse| {
+                inverse.transform_point([0.0, 0.0, 0.0])
+            })
+    }
+
+    // matrices multiply in reverse, this is `proj * view`
+    pub fn get_frustum(&self) -> Frustum {
+        Frustum::from_matrix(&(Matrix4(self.get_view()) * Matrix4(self.get_proj())))
+    }
+
     pub fn get_view(&self) -> [[f32; 4]; 4] {
         let position = self.position;
         let rotation = self.rotation;
@@ -67,3 +233,97 @@ impl Camera {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewport_rejects_values_outside_the_window() {
+        assert!(Viewport::new(0.5, 0.0, 0.5, 1.0).is_ok());
+        assert!(Viewport::new(f32::NAN, 0.0, 0.5, 1.0).is_err());
+        assert!(Viewport::new(0.0, 0.0, f32::INFINITY, 1.0).is_err());
+        assert!(Viewport::new(-0.1, 0.0, 0.5, 1.0).is_err());
+        assert!(Viewport::new(0.0, 0.0, 1.5, 1.0).is_err());
+        assert!(Viewport::new(0.6, 0.0, 0.5, 1.0).is_err());
+        assert!(Viewport::new(0.0, 0.5, 1.0, 0.75).is_err());
+    }
+
+    fn pixels(viewports: &[Viewport]) -> Vec<(u32, u32, u32, u32)> {
+        viewports
+            .iter()
+            .map(|viewport| {
+                let rect = viewport.to_pixels((800, 600));
+                (rect.left, rect.bottom, rect.width, rect.height)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn split_screen_tiles_the_window() {
+        assert_eq!(pixels(&Viewport::split_screen(1)), vec![(0, 0, 800, 600)]);
+        assert_eq!(
+            pixels(&Viewport::split_screen(2)),
+            vec![(0, 0, 400, 600), (400, 0, 400, 600)]
+        );
+        // three players share a 2x2 grid, leaving its bottom right empty
+        assert_eq!(
+            pixels(&Viewport::split_screen(3)),
+            vec![(0, 300, 400, 300), (400, 300, 400, 300), (0, 0, 400, 300)]
+        );
+        assert_eq!(
+            pixels(&Viewport::split_screen(4)),
+            vec![
+                (0, 300, 400, 300),
+                (400, 300, 400, 300),
+                (0, 0, 400, 300),
+                (400, 0, 400, 300)
+            ]
+        );
+
+        for count in 1..=9 {
+            for viewport in Viewport::split_screen(count) {
+                let Viewport {
+                    x,
+                    y,
+                    width,
+                    height,
+                } = viewport;
+                assert!(Viewport::new(x, y, width, height).is_ok(), "{:?}", viewport);
+            }
+        }
+    }
+
+    #[test]
+    fn split_screen_fits_every_camera() {
+        for count in 1..=4 {
+            let mut cameras: Vec<Camera> = (0..count)
+                .map(|_| Camera::new([0.0; 3], [0.0; 3], 90.0, 1.0, 0.1, 100.0))
+                .collect();
+            split_screen(cameras.iter_mut().collect(), (800, 600));
+
+            let viewports = Viewport::split_screen(count);
+            for (camera, viewport) in cameras.iter().zip(&viewports) {
+                assert_eq!(camera.viewport, *viewport);
+                let rect = viewport.to_pixels((800, 600));
+                assert_eq!(camera.aspect_ratio, rect.width as f32 / rect.height as f32);
+            }
+        }
+
+        let mut cameras = [
+            Camera::new([0.0; 3], [0.0; 3], 90.0, 1.0, 0.1, 100.0),
+            Camera::new([0.0; 3], [0.0; 3], 90.0, 1.0, 0.1, 100.0),
+        ];
+        split_screen(cameras.iter_mut().collect(), (800, 600));
+        assert_eq!(cameras[0].aspect_ratio, 400.0 / 600.0);
+    }
+
+    #[test]
+    fn nan_viewport_has_no_pixels() {
+        let viewport = Viewport {
+            x: f32::NAN,
+            ..Viewport::FULL
+        };
+        assert_eq!(viewport.to_pixels((800, 600)).width, 0);
+    }
+}
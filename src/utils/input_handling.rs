@@ -8,6 +8,14 @@ pub struct InputHandler {
     pub mouse_pos: (f64, f64),
     pub prev_mouse_pos: (f64, f64),
     pub mouse_delta: (f64, f64),
+    pub mouse_buttons: u8, // left, right and middle are bits 0, 1 and 2
+
+    // characters typed during the last frame, including control characters like backspace
+    pub text_input: String,
+    // lines scrolled during the last frame, positive is up
+    pub scroll_delta: f64,
+    pending_text: String,
+    pending_scroll: f64,
 
     pub callbacks: std::collections::HashMap<VirtualKeyCode, Vec<Box<dyn FnMut()>>>,
 }
@@ -20,6 +28,11 @@ impl InputHandler {
             mouse_pos: (0.0, 0.0),
             prev_mouse_pos: (0.0, 0.0),
             mouse_delta: (0.0, 0.0),
+            mouse_buttons: 0,
+            text_input: String::new(),
+            scroll_delta: 0.0,
+            pending_text: String::new(),
+            pending_scroll: 0.0,
             callbacks: std::collections::HashMap::new(),
         }
     }
@@ -45,6 +58,29 @@ impl InputHandler {
         self.keys[key as usize / 8] & (1 << (key as usize % 8)) != 0
     }
 
+    pub fn handle_mouse_button(&mut self, button: MouseButton, state: ElementState) {
+        let bit = match button {
+            MouseButton::Left => 0,
+            MouseButton::Right => 1,
+            MouseButton::Middle => 2,
+            MouseButton::Other(_) => return,
+        };
+
+        match state {
+            ElementState::Pressed => self.mouse_buttons |= 1 << bit,
+            ElementState::Released => self.mouse_buttons &= !(1 << bit),
+        }
+    }
+
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        match button {
+            MouseButton::Left => self.mouse_buttons & 1 != 0,
+            MouseButton::Right => self.mouse_buttons & 2 != 0,
+            MouseButton::Middle => self.mouse_buttons & 4 != 0,
+            MouseButton::Other(_) => false,
+        }
+    }
+
     pub fn register_callback(&mut self, key: VirtualKeyCode, callback: Box<dyn FnMut()>) {
         if let Some(callbacks) = self.callbacks.get_mut(&key) {
             callbacks.push(callback);
@@ -54,14 +90,60 @@ impl InputHandler {
     }
 }
 
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+        app.add_resource(InputHandler::new())
+            .add_system(get_input_handler_system(), SystemType::Update)
+            .add_window_event_handler(|event, game_state| {
+                let input_handler = match game_state.get_resource_mut::<InputHandler>() {
+                    Some(input_handler) => input_handler,
+                    None => return,
+                };
+
+                match event {
+                    WindowEvent::KeyboardInput { input, .. } => {
+                        if let Some(key) = input.virtual_keycode {
+                            input_handler.handle_key_press(key, input.state);
+                        }
+                    }
+                    WindowEvent::CursorMoved { position, .. } => {
+                        input_handler.mouse_pos = (position.x, position.y);
+                    }
+                    WindowEvent::MouseInput { state, button, .. } => {
+                        input_handler.handle_mouse_button(*button, *state);
+                    }
+                    WindowEvent::ReceivedCharacter(c) => input_handler.pending_text.push(*c),
+                    WindowEvent::MouseWheel { delta, .. } => {
+                        input_handler.pending_scroll += match delta {
+                            MouseScrollDelta::LineDelta(_, y) => *y as f64,
+                            // roughly a line of text
+                            MouseScrollDelta::PixelDelta(position) => position.y / 20.0,
+                        };
+                    }
+                    _ => (),
+                }
+            });
+
+        Ok(())
+    }
+}
+
 create_system!(periodic, get_input_handler_system;
     uses InputHandler);
-pub async fn periodic(game_state: &mut GameState, _t: f64, _dt: f64) {
-    let input_handler = game_state.get_resource_mut::<InputHandler>().unwrap();
+pub async fn periodic(game_state: &mut GameState, _t: f64, _dt: f64) -> SystemResult {
+    let input_handler = game_state.require_resource_mut::<InputHandler>()?;
 
     input_handler.mouse_delta = (
         input_handler.mouse_pos.0 - input_handler.prev_mouse_pos.0,
         input_handler.mouse_pos.1 - input_handler.prev_mouse_pos.1,
     );
     input_handler.prev_mouse_pos = input_handler.mouse_pos;
+
+    // events arrive between frames, every system sees the same ones for a whole frame
+    input_handler.text_input = std::mem::take(&mut input_handler.pending_text);
+    input_handler.scroll_delta = std::mem::take(&mut input_handler.pending_scroll);
+
+    Ok(())
 }
@@ -0,0 +1,79 @@
+use super::matrix::Matrix4;
+
+// an axis aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Bounds {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Bounds {
+    // zero sized at the origin if there are no points
+    pub fn from_points(points: impl IntoIterator<Item = [f32; 3]>) -> Bounds {
+        let mut points = points.into_iter();
+        let first = match points.next() {
+            Some(first) => first,
+            None => return Bounds::default(),
+        };
+
+        points.fold(
+            Bounds {
+                min: first,
+                max: first,
+            },
+            |bounds, point| Bounds {
+                min: [
+                    bounds.min[0].min(point[0]),
+                    bounds.min[1].min(point[1]),
+                    bounds.min[2].min(point[2]),
+                ],
+                max: [
+                    bounds.max[0].max(point[0]),
+                    bounds.max[1].max(point[1]),
+                    bounds.max[2].max(point[2]),
+                ],
+            },
+        )
+    }
+
+    pub fn center(&self) -> [f32; 3] {
+        [
+            (self.min[0] + self.max[0]) * 0.5,
+            (self.min[1] + self.max[1]) * 0.5,
+            (self.min[2] + self.max[2]) * 0.5,
+        ]
+    }
+
+    // of the bounding sphere around `center`
+    pub fn radius(&self) -> f32 {
+        let size = [
+            self.max[0] - self.min[0],
+            self.max[1] - self.min[1],
+            self.max[2] - self.min[2],
+        ];
+        (size[0] * size[0] + size[1] * size[1] + size[2] * size[2]).sqrt() * 0.5
+    }
+
+    pub fn corners(&self) -> [[f32; 3]; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            [min[0], min[1], min[2]],
+            [max[0], min[1], min[2]],
+            [min[0], max[1], min[2]],
+            [max[0], max[1], min[2]],
+            [min[0], min[1], max[2]],
+            [max[0], min[1], max[2]],
+            [min[0], max[1], max[2]],
+            [max[0], max[1], max[2]],
+        ]
+    }
+
+    // the box around the transformed corners, so it grows when rotated
+    pub fn transformed(&self, matrix: &Matrix4) -> Bounds {
+        Bounds::from_points(
+            self.corners()
+                .into_iter()
+                .map(|corner| matrix.transform_point(corner)),
+        )
+    }
+}
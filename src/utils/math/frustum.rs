@@ -0,0 +1,146 @@
+use super::bounds::Bounds;
+use super::matrix::Matrix4;
+use super::vector::dot;
+
+// the six planes around what a camera can see, with their normals pointing inwards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    // xyz is the normal, w the distance along it from the origin
+    pub planes: [[f32; 4]; 6],
+}
+
+impl Frustum {
+    // `matrix` takes world space to clip space, like a camera's `view * proj`
+    pub fn from_matrix(matrix: &Matrix4) -> Frustum {
+        let m = &matrix.0;
+        let row = |i: usize| [m[0][i], m[1][i], m[2][i], m[3][i]];
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+
+        let add = |a: [f32; 4], b: [f32; 4]| [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]];
+        let sub = |a: [f32; 4], b: [f32; 4]| [a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]];
+
+        let planes = [
+            add(w, x),
+            sub(w, x),
+            add(w, y),
+            sub(w, y),
+            add(w, z),
+            sub(w, z),
+        ]
+        .map(|plane| {
+            let length = dot(
+                [plane[0], plane[1], plane[2]],
+                [plane[0], plane[1], plane[2]],
+            )
+            .sqrt();
+            [
+                plane[0] / length,
+                plane[1] / length,
+                plane[2] / length,
+                plane[3] / length,
+            ]
+        });
+
+        Frustum { planes }
+    }
+
+    pub fn intersects_sphere(&self, center: [f32; 3], radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| dot([plane[0], plane[1], plane[2]], center) + plane[3] >= -radius)
+    }
+
+    // can give false positives near the frustum's corners, never false negatives
+    pub fn intersects_bounds(&self, bounds: &Bounds) -> bool {
+        if !self.intersects_sphere(bounds.center(), bounds.radius()) {
+            return false;
+        }
+
+        // the corner furthest along each plane's normal has to be inside it
+        self.planes.iter().all(|plane| {
+            let corner = [
+                if plane[0] >= 0.0 {
+                    bounds.max[0]
+                } else {
+                    bounds.min[0]
+                },
+                if plane[1] >= 0.0 {
+                    bounds.max[1]
+                } else {
+                    bounds.min[1]
+                },
+                if plane[2] >= 0.0 {
+                    bounds.max[2]
+                } else {
+                    bounds.min[2]
+                },
+            ];
+            dot([plane[0], plane[1], plane[2]], corner) + plane[3] >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::camera::Camera;
+
+    // at the origin looking down -z, 90 degrees wide, 0.1 to 100 deep
+    fn frustum() -> Frustum {
+        Camera::new([0.0; 3], [0.0; 3], 90.0, 1.0, 0.1, 100.0).get_frustum()
+    }
+
+    fn cube(center: [f32; 3], half_size: f32) -> Bounds {
+        Bounds {
+            min: center.map(|c| c - half_size),
+            max: center.map(|c| c + half_size),
+        }
+    }
+
+    #[test]
+    fn bounds_in_front_are_visible() {
+        assert!(frustum().intersects_bounds(&cube([0.0, 0.0, -5.0], 1.0)));
+        assert!(frustum().intersects_bounds(&cube([4.0, -4.0, -5.0], 0.5)));
+    }
+
+    #[test]
+    fn bounds_outside_are_culled() {
+        let frustum = frustum();
+        // behind, past the far plane and off to each side
+        for center in [
+            [0.0, 0.0, 5.0],
+            [0.0, 0.0, -200.0],
+            [20.0, 0.0, -5.0],
+            [-20.0, 0.0, -5.0],
+            [0.0, 20.0, -5.0],
+            [0.0, -20.0, -5.0],
+        ] {
+            assert!(
+                !frustum.intersects_bounds(&cube(center, 1.0)),
+                "{:?}",
+                center
+            );
+        }
+    }
+
+    #[test]
+    fn bounds_crossing_a_plane_are_visible() {
+        let frustum = frustum();
+        assert!(frustum.intersects_bounds(&cube([0.0; 3], 1.0)));
+        assert!(frustum.intersects_bounds(&cube([6.0, 0.0, -5.0], 1.5)));
+        assert!(frustum.intersects_bounds(&cube([0.0, 0.0, -100.0], 1.0)));
+    }
+
+    #[test]
+    fn transformed_bounds_are_culled_where_they_end_up() {
+        let translation = |z: f32| {
+            let mut matrix = Matrix4::IDENTITY;
+            matrix.0[3][2] = z;
+            matrix
+        };
+        let bounds = cube([0.0; 3], 1.0);
+
+        assert!(frustum().intersects_bounds(&bounds.transformed(&translation(-10.0))));
+        assert!(!frustum().intersects_bounds(&bounds.transformed(&translation(10.0))));
+    }
+}
@@ -1,3 +1,6 @@
+use super::vector::{cross, dot, normalize};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Matrix4(pub [[f32; 4]; 4]);
 
 impl std::ops::Mul for Matrix4 {
@@ -18,3 +21,112 @@ impl std::ops::Mul for Matrix4 {
         Self(result)
     }
 }
+
+impl Matrix4 {
+    pub const IDENTITY: Matrix4 = Matrix4([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    pub fn look_at(eye: [f32; 3], target: [f32; 3], up: [f32; 3]) -> Matrix4 {
+        let f = normalize([target[0] - eye[0], target[1] - eye[1], target[2] - eye[2]]);
+        let s = normalize(cross(f, up));
+        let u = cross(s, f);
+
+        Matrix4([
+            [s[0], u[0], -f[0], 0.0],
+            [s[1], u[1], -f[1], 0.0],
+            [s[2], u[2], -f[2], 0.0],
+            [-dot(s, eye), -dot(u, eye), dot(f, eye), 1.0],
+        ])
+    }
+
+    pub fn orthographic(half_width: f32, half_height: f32, near: f32, far: f32) -> Matrix4 {
+        Matrix4([
+            [1.0 / half_width, 0.0, 0.0, 0.0],
+            [0.0, 1.0 / half_height, 0.0, 0.0],
+            [0.0, 0.0, -2.0 / (far - near), 0.0],
+            [0.0, 0.0, -(far + near) / (far - near), 1.0],
+        ])
+    }
+
+    // `fov` is vertical and in radians
+    pub fn perspective(fov: f32, aspect_ratio: f32, near: f32, far: f32) -> Matrix4 {
+        let f = 1.0 / (fov / 2.0).tan();
+
+        Matrix4([
+            [f / aspect_ratio, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, (far + near) / (near - far), -1.0],
+            [0.0, 0.0, (2.0 * far * near) / (near - far), 0.0],
+        ])
+    }
+
+    // `None` if the matrix can't be inverted
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let m = &self.0;
+
+        let s0 = m[0][0] * m[1][1] - m[1][0] * m[0][1];
+        let s1 = m[0][0] * m[1][2] - m[1][0] * m[0][2];
+        let s2 = m[0][0] * m[1][3] - m[1][0] * m[0][3];
+        let s3 = m[0][1] * m[1][2] - m[1][1] * m[0][2];
+        let s4 = m[0][1] * m[1][3] - m[1][1] * m[0][3];
+        let s5 = m[0][2] * m[1][3] - m[1][2] * m[0][3];
+
+        let c5 = m[2][2] * m[3][3] - m[3][2] * m[2][3];
+        let c4 = m[2][1] * m[3][3] - m[3][1] * m[2][3];
+        let c3 = m[2][1] * m[3][2] - m[3][1] * m[2][2];
+        let c2 = m[2][0] * m[3][3] - m[3][0] * m[2][3];
+        let c1 = m[2][0] * m[3][2] - m[3][0] * m[2][2];
+        let c0 = m[2][0] * m[3][1] - m[3][0] * m[2][1];
+
+        let det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let inv = 1.0 / det;
+
+        Some(Matrix4([
+            [
+                (m[1][1] * c5 - m[1][2] * c4 + m[1][3] * c3) * inv,
+                (-m[0][1] * c5 + m[0][2] * c4 - m[0][3] * c3) * inv,
+                (m[3][1] * s5 - m[3][2] * s4 + m[3][3] * s3) * inv,
+                (-m[2][1] * s5 + m[2][2] * s4 - m[2][3] * s3) * inv,
+            ],
+            [
+                (-m[1][0] * c5 + m[1][2] * c2 - m[1][3] * c1) * inv,
+                (m[0][0] * c5 - m[0][2] * c2 + m[0][3] * c1) * inv,
+                (-m[3][0] * s5 + m[3][2] * s2 - m[3][3] * s1) * inv,
+                (m[2][0] * s5 - m[2][2] * s2 + m[2][3] * s1) * inv,
+            ],
+            [
+                (m[1][0] * c4 - m[1][1] * c2 + m[1][3] * c0) * inv,
+                (-m[0][0] * c4 + m[0][1] * c2 - m[0][3] * c0) * inv,
+                (m[3][0] * s4 - m[3][1] * s2 + m[3][3] * s0) * inv,
+                (-m[2][0] * s4 + m[2][1] * s2 - m[2][3] * s0) * inv,
+            ],
+            [
+                (-m[1][0] * c3 + m[1][1] * c1 - m[1][2] * c0) * inv,
+                (m[0][0] * c3 - m[0][1] * c1 + m[0][2] * c0) * inv,
+                (-m[3][0] * s3 + m[3][1] * s1 - m[3][2] * s0) * inv,
+                (m[2][0] * s3 - m[2][1] * s1 + m[2][2] * s0) * inv,
+            ],
+        ]))
+    }
+
+    // treats the matrix the way the shaders do, with the translation in the last array
+    pub fn transform_point(&self, point: [f32; 3]) -> [f32; 3] {
+        let m = &self.0;
+        let mut result = [0.0; 4];
+        for (i, value) in result.iter_mut().enumerate() {
+            *value = m[0][i] * point[0] + m[1][i] * point[1] + m[2][i] * point[2] + m[3][i];
+        }
+        [
+            result[0] / result[3],
+            result[1] / result[3],
+            result[2] / result[3],
+        ]
+    }
+}
@@ -1,3 +1,9 @@
+pub mod bounds;
+pub use bounds::*;
+
+pub mod frustum;
+pub use frustum::*;
+
 pub mod matrix;
 pub use matrix::*;
 
@@ -3,3 +3,15 @@ pub fn normalize(v: [f32; 3]) -> [f32; 3] {
     let len = len.sqrt();
     [v[0] / len, v[1] / len, v[2] / len]
 }
+
+pub fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
@@ -1,21 +1,74 @@
 use crate::core::*;
-use glium::{glutin::surface::WindowSurface, *};
-use std::{any::Any, collections::HashMap};
+use crate::utils::math::bounds::Bounds;
+use glium::{backend::Facade, *};
+use std::{any::Any, collections::HashMap, rc::Rc};
 
 #[derive(Copy, Clone, Debug)]
 pub struct Vertex {
     position: [f32; 3],
     normal: [f32; 3],
     uv: [f32; 2],
+    // w is the sign of the bitangent, `cross(normal, tangent) * w`
+    tangent: [f32; 4],
 }
 
-implement_vertex!(Vertex, position, normal, uv);
+implement_vertex!(Vertex, position, normal, uv, tangent);
+
+impl Vertex {
+    // the bits of every field, so identical vertices can be merged
+    fn key(&self) -> [u32; 12] {
+        let mut key = [0; 12];
+        let values = self
+            .position
+            .iter()
+            .chain(&self.normal)
+            .chain(&self.uv)
+            .chain(&self.tangent);
+        for (k, value) in key.iter_mut().zip(values) {
+            *k = value.to_bits();
+        }
+        key
+    }
+}
+
+// unindexed triangles, mikktspace can give a vertex shared by several faces a different tangent
+// on each of them
+struct Triangles(Vec<Vertex>);
+
+impl bevy_mikktspace::Geometry for Triangles {
+    fn num_faces(&self) -> usize {
+        self.0.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.0[face * 3 + vert].position
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.0[face * 3 + vert].normal
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.0[face * 3 + vert].uv
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.0[face * 3 + vert].tangent = tangent;
+    }
+}
 
 // component type 5
-#[derive(Debug)]
+// cloning shares the buffers, entities with clones of one model are drawn instanced
+#[derive(Debug, Clone)]
 pub struct Model {
-    pub vertices: VertexBuffer<Vertex>,
-    pub indices: IndexBuffer<u32>,
+    pub vertices: Rc<VertexBuffer<Vertex>>,
+    pub indices: Rc<IndexBuffer<u32>>,
+    // in model space, for culling
+    pub bounds: Bounds,
 }
 impl_component!(Model, 5);
 
@@ -26,15 +79,32 @@ macro_rules! err {
     };
 }
 
-pub async fn parse_object(
+pub async fn parse_object<F: Facade + ?Sized>(
     path: &str,
-    display: &glium::Display<WindowSurface>,
+    display: &F,
 ) -> Result<Model, Box<dyn std::error::Error>> {
-    let mut vertices: Vec<(u32, u32, u32)> = Vec::new(); // (position, normal, uv)
-    let mut vertex_index_map: HashMap<(u32, u32, u32), u32> = HashMap::new();
-    let mut indices: Vec<u32> = Vec::new();
-
     let contents = std::fs::read_to_string(path)?;
+    let (vertices, indices) = parse_mesh(&contents, path)?;
+
+    let bounds = Bounds::from_points(vertices.iter().map(|vertex| vertex.position));
+
+    Ok(Model {
+        bounds,
+        vertices: Rc::new(VertexBuffer::new(display, &vertices)?),
+        indices: Rc::new(IndexBuffer::new(
+            display,
+            glium::index::PrimitiveType::TrianglesList,
+            &indices,
+        )?),
+    })
+}
+
+// everything up to the upload, `name` is only used in messages
+fn parse_mesh(
+    contents: &str,
+    name: &str,
+) -> Result<(Vec<Vertex>, Vec<u32>), Box<dyn std::error::Error>> {
+    let mut corners: Vec<[u32; 3]> = Vec::new(); // (position, uv, normal) for every triangle corner
 
     let lines = contents.lines();
 
@@ -62,36 +132,25 @@ pub async fn parse_object(
                 ]);
             }
             Some("f") => {
-                let f: Vec<[u32; 3]> = words
-                    .map(|x| //maps each word to a [u32; 3]
-                    x
-                        .split("/")
-                        .map(|y| y.parse::<u32>().unwrap() - 1) //subtracts 1 from each index to make it 0-based instead of 1-based
-                        .collect::<Vec<u32>>()
-                        .try_into()
-                        .unwrap())
-                    .collect();
-
-                let handle_vertex = |x: [u32; 3]| {
-                    let x = (x[0], x[1], x[2]);
-
-                    if let Some(index) = vertex_index_map.get(&x) {
-                        indices.push(*index);
-                        return;
-                    }
+                let f = words
+                    .map(|x| -> Result<[u32; 3], Box<dyn std::error::Error>> {
+                        //maps each word to a [u32; 3]
+                        let indices = x
+                            .split("/")
+                            .map(|y| -> Result<u32, Box<dyn std::error::Error>> {
+                                //subtracts 1 from each index to make it 0-based instead of 1-based
+                                Ok(y.parse::<u32>()?.checked_sub(1).ok_or(err!())?)
+                            })
+                            .collect::<Result<Vec<u32>, _>>()?;
+                        Ok(indices.try_into().map_err(|_| err!())?)
+                    })
+                    .collect::<Result<Vec<[u32; 3]>, _>>()?;
 
-                    let index = vertices.len() as u32;
-                    vertices.push(x);
-                    indices.push(index);
-                    vertex_index_map.insert(x, index);
-                };
                 match f.len() {
-                    3 => f.into_iter().for_each(handle_vertex),
+                    3 => corners.extend(f),
                     4 => {
                         //converts the quad into two triangles
-                        [f[0], f[1], f[2], f[0], f[2], f[3]]
-                            .into_iter()
-                            .for_each(handle_vertex);
+                        corners.extend([f[0], f[1], f[2], f[0], f[2], f[3]]);
                     }
                     _ => return Err(Box::new(err!())),
                 }
@@ -113,27 +172,101 @@ pub async fn parse_object(
         }
     }
 
-    let vertices = vertices
-        .iter()
-        .map(|x| {
-            let position = vertex_positions[x.0 as usize];
-            let normal = vertex_normals[x.2 as usize];
-            let uv = vertex_uvs[x.1 as usize];
-
-            Vertex {
-                position,
-                normal,
-                uv,
+    let mut triangles = Triangles(
+        corners
+            .iter()
+            .map(|x| {
+                let position = *vertex_positions.get(x[0] as usize).ok_or(err!())?;
+                let normal = *vertex_normals.get(x[2] as usize).ok_or(err!())?;
+                let uv = *vertex_uvs.get(x[1] as usize).ok_or(err!())?;
+
+                Ok(Vertex {
+                    position,
+                    normal,
+                    uv,
+                    tangent: [0.0; 4],
+                })
+            })
+            .collect::<Result<Vec<Vertex>, std::io::Error>>()?,
+    );
+
+    // leaves the tangents zeroed, which the shaders treat as having no normal map
+    if !bevy_mikktspace::generate_tangents(&mut triangles) {
+        eprintln!("Error generating tangents for {}", name);
+    }
+
+    // merges corners that ended up identical
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut vertex_index_map: HashMap<[u32; 12], u32> = HashMap::new();
+    let mut indices: Vec<u32> = Vec::with_capacity(triangles.0.len());
+
+    for vertex in triangles.0 {
+        let index = *vertex_index_map.entry(vertex.key()).or_insert_with(|| {
+            vertices.push(vertex);
+            vertices.len() as u32 - 1
+        });
+        indices.push(index);
+    }
+
+    Ok((vertices, indices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a unit quad facing +z, `uvs` are given for its corners counter-clockwise from the origin
+    fn quad(uvs: [[f32; 2]; 4]) -> String {
+        let mut obj = String::from("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\n");
+        for [u, v] in uvs {
+            obj += &format!("vt {} {}\n", u, v);
+        }
+        obj + "f 1/1/1 2/2/1 3/3/1 4/4/1\n"
+    }
+
+    fn assert_tangents(vertices: &[Vertex], expected: [f32; 4]) {
+        for vertex in vertices {
+            for (actual, expected) in vertex.tangent.iter().zip(expected) {
+                assert!(
+                    (actual - expected).abs() < 1e-5,
+                    "tangent {:?}, expected {:?}",
+                    vertex.tangent,
+                    expected
+                );
             }
-        })
-        .collect::<Vec<Vertex>>();
+        }
+    }
 
-    Ok(Model {
-        vertices: VertexBuffer::new(display, &vertices)?,
-        indices: IndexBuffer::new(
-            display,
-            glium::index::PrimitiveType::TrianglesList,
-            &indices,
-        )?,
-    })
+    #[test]
+    fn quad_tangents_follow_the_uvs() {
+        let (vertices, indices) = parse_mesh(
+            &quad([[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]),
+            "quad",
+        )
+        .unwrap();
+
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_tangents(&vertices, [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn mirrored_uvs_flip_the_bitangent_sign() {
+        // v runs down the quad, so the bitangent points along -y
+        let (vertices, _) = parse_mesh(
+            &quad([[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]]),
+            "quad",
+        )
+        .unwrap();
+
+        assert_tangents(&vertices, [1.0, 0.0, 0.0, -1.0]);
+    }
+
+    #[test]
+    fn malformed_faces_are_errors() {
+        let obj = "v 0 0 0\nvt 0 0\nvn 0 0 1\n";
+        assert!(parse_mesh(&format!("{}f 1/1/1 1/1/1\n", obj), "bad").is_err());
+        assert!(parse_mesh(&format!("{}f 0/1/1 1/1/1 1/1/1\n", obj), "bad").is_err());
+        assert!(parse_mesh(&format!("{}f 2/1/1 1/1/1 1/1/1\n", obj), "bad").is_err());
+    }
 }
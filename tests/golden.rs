@@ -0,0 +1,27 @@
+use std::process::Command;
+
+// renders the teapot scene headless and compares it against tests/golden/teapot.png
+#[test]
+fn teapot_matches_golden_image() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cryptid_squad"))
+        .args(["--golden", "teapot"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env_remove("UPDATE_GOLDEN")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if stderr.contains("No headless OpenGL context") {
+        eprintln!("skipping golden image test, no OpenGL context: {}", stderr);
+        return;
+    }
+
+    assert!(
+        output.status.success(),
+        "golden run failed\nstdout:\n{}\nstderr:\n{}",
+        stdout,
+        stderr
+    );
+    assert!(stdout.contains("teapot matches"), "stdout:\n{}", stdout);
+}